@@ -0,0 +1,241 @@
+use crate::FILES;
+use crate::file::{File, FileType};
+use crate::uid::Uid;
+use crate::utils::{get_file_by_uid, try_get_path_by_uid};
+use lazy_static::lazy_static;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::io::{self, Read};
+use std::sync::{Arc, Mutex};
+use zip::ZipArchive;
+
+// Members of an archive are synthetic `File`s. They live in `FILES`, but
+// they don't exist in the real file system. Their paths look like
+// `/path/to/archive.zip/dir/member.txt`.
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum NavigationMode {
+    FileSystem,
+    Archive {
+        archive: Uid,  // the `.zip` file in the real file system
+        root: Uid,     // the synthetic dir that represents the archive
+    },
+}
+
+// the file viewer reads a member on every render, so the decompressed members are cached
+const MAX_CACHED_MEMBERS: usize = 8;
+
+struct ArchiveMember {
+    archive_path: String,
+    index: usize,  // index in the zip archive
+}
+
+lazy_static! {
+    // every synthetic file and dir, including the roots
+    static ref ARCHIVE_FILES: Mutex<HashSet<Uid>> = Mutex::new(HashSet::new());
+
+    // only files (not dirs) have their contents
+    static ref ARCHIVE_MEMBERS: Mutex<HashMap<Uid, ArchiveMember>> = Mutex::new(HashMap::new());
+
+    // path of an archive -> uid of its root
+    // it doesn't read the same archive multiple times
+    static ref ARCHIVE_ROOTS: Mutex<HashMap<String, Uid>> = Mutex::new(HashMap::new());
+
+    // the most recently read member comes first
+    static ref MEMBER_CONTENTS: Mutex<VecDeque<(Uid, Arc<[u8]>)>> = Mutex::new(VecDeque::with_capacity(MAX_CACHED_MEMBERS));
+}
+
+// it returns the uid of the synthetic root dir
+pub fn enter_archive(archive: Uid) -> Result<Uid, String> {
+    let archive_path = match try_get_path_by_uid(archive) {
+        Some(path) => path.to_string(),
+        None => {
+            return Err(format!("get_path_by_uid({archive}) has failed"));
+        },
+    };
+
+    if let Some(root) = ARCHIVE_ROOTS.lock().unwrap().get(&archive_path) {
+        return Ok(*root);
+    }
+
+    debug_assert_uid_valid!(archive);
+    let archive_file = get_file_by_uid(archive).unwrap();
+    let mut zip = match fs::File::open(&archive_path) {
+        Ok(f) => match ZipArchive::new(f) {
+            Ok(zip) => zip,
+            Err(e) => {
+                return Err(e.to_string());
+            },
+        },
+        Err(e) => {
+            return Err(format!("{e:?}"));
+        },
+    };
+
+    let root = register_synthetic_file(File {
+        parent: Some(archive_file.get_parent_uid()),
+        name: archive_file.name.clone(),
+        last_modified: archive_file.last_modified,
+        file_type: FileType::Dir,
+        children: Some(vec![]),
+        ..File::dummy()
+    });
+
+    // "dir/sub_dir" -> uid
+    let mut dirs = HashMap::new();
+    dirs.insert(String::new(), root);
+
+    let mut members = ARCHIVE_MEMBERS.lock().unwrap();
+
+    for index in 0..zip.len() {
+        let member = match zip.by_index(index) {
+            Ok(member) => member,
+            Err(_) => {
+                continue;
+            },
+        };
+        let is_dir = member.is_dir();
+        let size = member.size();
+        let name = member.name().trim_end_matches('/').to_string();
+        let components = name.split('/').filter(|c| !c.is_empty()).collect::<Vec<_>>();
+
+        if components.is_empty() {
+            continue;
+        }
+
+        let mut parent = root;
+
+        // zip files do not always have entries for intermediate dirs
+        for depth in 0..(components.len() - 1) {
+            parent = get_or_create_dir(&mut dirs, &components[..(depth + 1)], parent, archive_file.last_modified);
+        }
+
+        if is_dir {
+            get_or_create_dir(&mut dirs, &components, parent, archive_file.last_modified);
+        }
+
+        else {
+            let file_name = components.last().unwrap().to_string();
+            let file_ext = file_name.rsplit_once('.').map(|(_, ext)| ext.to_string());
+            let uid = register_synthetic_file(File {
+                parent: Some(parent),
+                name: file_name,
+                last_modified: archive_file.last_modified,
+                size,
+                recursive_size: Some(size),
+                file_type: FileType::File,
+                file_ext,
+                ..File::dummy()
+            });
+
+            debug_assert_uid_valid!(parent);
+            get_file_by_uid(parent).unwrap().children.as_mut().unwrap().push(uid);
+            members.insert(uid, ArchiveMember { archive_path: archive_path.clone(), index });
+        }
+    }
+
+    ARCHIVE_ROOTS.lock().unwrap().insert(archive_path, root);
+
+    Ok(root)
+}
+
+// it's `true` for dirs inside archives and the roots of archives
+pub fn is_archive_member(uid: Uid) -> bool {
+    ARCHIVE_FILES.lock().unwrap().contains(&uid)
+}
+
+// It returns `None` if `uid` is not a member of an archive.
+// The contents are decompressed only once, unless they're evicted from the cache.
+pub fn read_archive_member(uid: Uid) -> Option<io::Result<Arc<[u8]>>> {
+    let (archive_path, index) = match ARCHIVE_MEMBERS.lock().unwrap().get(&uid) {
+        Some(member) => (member.archive_path.clone(), member.index),
+        None => {
+            return None;
+        },
+    };
+
+    {
+        let mut contents = MEMBER_CONTENTS.lock().unwrap();
+
+        if let Some(i) = contents.iter().position(|(uid_, _)| *uid_ == uid) {
+            let entry = contents.remove(i).unwrap();
+            let bytes = entry.1.clone();
+            contents.push_front(entry);
+
+            return Some(Ok(bytes));
+        }
+    }
+
+    let f = match fs::File::open(archive_path) {
+        Ok(f) => f,
+        Err(e) => {
+            return Some(Err(e));
+        },
+    };
+
+    let mut zip = match ZipArchive::new(f) {
+        Ok(zip) => zip,
+        Err(e) => {
+            return Some(Err(e.into()));
+        },
+    };
+
+    let mut member = match zip.by_index(index) {
+        Ok(member) => member,
+        Err(e) => {
+            return Some(Err(e.into()));
+        },
+    };
+
+    let mut buffer = Vec::with_capacity(member.size() as usize);
+
+    if let Err(e) = member.read_to_end(&mut buffer) {
+        return Some(Err(e));
+    }
+
+    let bytes: Arc<[u8]> = buffer.into();
+    let mut contents = MEMBER_CONTENTS.lock().unwrap();
+    contents.truncate(MAX_CACHED_MEMBERS - 1);
+    contents.push_front((uid, bytes.clone()));
+
+    Some(Ok(bytes))
+}
+
+fn get_or_create_dir(
+    dirs: &mut HashMap<String, Uid>,
+    components: &[&str],
+    parent: Uid,
+    last_modified: std::time::SystemTime,
+) -> Uid {
+    let key = components.join("/");
+
+    if let Some(uid) = dirs.get(&key) {
+        return *uid;
+    }
+
+    let uid = register_synthetic_file(File {
+        parent: Some(parent),
+        name: components.last().unwrap().to_string(),
+        last_modified,
+        file_type: FileType::Dir,
+        children: Some(vec![]),
+        ..File::dummy()
+    });
+
+    debug_assert_uid_valid!(parent);
+    get_file_by_uid(parent).unwrap().children.as_mut().unwrap().push(uid);
+    dirs.insert(key, uid);
+
+    uid
+}
+
+// it doesn't register the path to `PATHS`
+fn register_synthetic_file(file: File) -> Uid {
+    let uid = Uid::normal_file();
+    let files = unsafe { FILES.as_mut().unwrap() };
+
+    let is_hidden = file.name.starts_with('.');
+    files.insert(uid, File { uid, is_hidden, ..file });
+    ARCHIVE_FILES.lock().unwrap().insert(uid);
+
+    uid
+}
@@ -2,9 +2,26 @@ use colored::Color;
 
 pub const BLACK: Color = Color::TrueColor { r: 0, g: 0, b: 0 };
 pub const BLUE: Color = Color::TrueColor { r: 32, g: 32, b: 192 };
+pub const CYAN: Color = Color::TrueColor { r: 32, g: 192, b: 192 };
 pub const DARK_GRAY: Color = Color::TrueColor { r: 48, g: 48, b: 48 };
 pub const GRAY: Color = Color::TrueColor { r: 128, g: 128, b: 128 };
 pub const GREEN: Color = Color::TrueColor { r: 32, g: 192, b: 32 };
+pub const MAGENTA: Color = Color::TrueColor { r: 192, g: 32, b: 192 };
+pub const ORANGE: Color = Color::TrueColor { r: 224, g: 128, b: 32 };
+pub const PURPLE: Color = Color::TrueColor { r: 128, g: 64, b: 192 };
 pub const RED: Color = Color::TrueColor { r: 192, g: 32, b: 32 };
 pub const WHITE: Color = Color::TrueColor { r: 255, g: 255, b: 255 };
 pub const YELLOW: Color = Color::TrueColor { r: 192, g: 192, b: 32 };
+
+// a darker version of `color`, for backgrounds
+pub fn dim(color: Color) -> Color {
+    match color {
+        Color::TrueColor { r, g, b } => Color::TrueColor { r: r / 4, g: g / 4, b: b / 4 },
+        _ => DARK_GRAY,
+    }
+}
+
+// background of secondary rows, like the children of nested dirs
+pub fn secondary_background() -> Color {
+    DARK_GRAY
+}
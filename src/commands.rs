@@ -0,0 +1,1171 @@
+use crate::{
+    clear_grep_results,
+    follow_symlink,
+    format_wrap_alert,
+    get_adjacent_sibling,
+    parse_int_from,
+    MAX_COLUMN_MARGIN,
+    MAX_GOTO_INDEX,
+    MAX_NESTED_LEVEL,
+};
+use colored::Color;
+use hfile::*;
+use regex::Regex;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+// `;c` updates its progress at most this often
+const COPY_PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+// `;D` updates its progress at most this often
+const CHECKSUM_PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+// states of the main loop that the commands read and write
+pub struct AppState {
+    pub curr_uid: Uid,
+    pub curr_instance: &'static mut File,
+    pub navigation_mode: NavigationMode,
+    pub print_dir_config: PrintDirConfig,
+    pub print_file_config: PrintFileConfig,
+    pub print_link_config: PrintLinkConfig,
+    pub history: History,
+}
+
+impl AppState {
+    // `uid` must be registered
+    pub fn set_curr_uid(&mut self, uid: Uid) {
+        debug_assert_uid_valid!(uid);
+        self.curr_uid = uid;
+        self.curr_instance = get_file_by_uid(uid).unwrap();
+    }
+}
+
+// what a command accepts after its name
+#[derive(Clone, Copy)]
+pub enum CommandArgs {
+    // `;E`
+    Nothing,
+    // `;m4`
+    Number,
+    // `;j` or `;j5`
+    OptionalNumber,
+    // `;G foo`, anything after the name
+    Text,
+}
+
+impl CommandArgs {
+    pub fn accepts(&self, args: &str) -> bool {
+        match self {
+            CommandArgs::Nothing => args.is_empty(),
+            CommandArgs::Number => !args.is_empty() && args.chars().all(|c| c.is_ascii_digit()),
+            CommandArgs::OptionalNumber => args.chars().all(|c| c.is_ascii_digit()),
+            CommandArgs::Text => true,
+        }
+    }
+}
+
+pub struct Command {
+    pub args: CommandArgs,
+
+    // the second argument is the input after the name of the command
+    pub run: Box<dyn Fn(&mut AppState, &str)>,
+}
+
+pub type CommandTable = HashMap<&'static str, Command>;
+
+fn command(args: CommandArgs, run: impl Fn(&mut AppState, &str) + 'static) -> Command {
+    Command { args, run: Box::new(run) }
+}
+
+// `input` is what comes after `;`
+// the longest name goes first, so `;jj` is not `;j` with an arg `j`
+// if a name matches but its args don't, it tries the shorter names
+// it returns false if no command matches
+pub fn run_command(table: &CommandTable, state: &mut AppState, input: &str) -> bool {
+    let mut names = table.keys().filter(
+        |name| input.starts_with(**name)
+    ).collect::<Vec<_>>();
+    names.sort_by_key(|name| Reverse(name.len()));
+
+    for name in names.into_iter() {
+        let command = &table[name];
+        let args = &input[name.len()..];
+
+        if command.args.accepts(args) {
+            (command.run)(state, args);
+            return true;
+        }
+    }
+
+    false
+}
+
+// tab-completion of the commands that take a path
+// `;c ../Mus` -> `;c ../Music/`
+pub fn complete_command(base: Uid, input: &str) -> Vec<String> {
+    for name in [";c "] {
+        if let Some(path) = input.strip_prefix(name) {
+            return complete_path(base, path).into_iter().map(
+                |path| format!("{name}{path}")
+            ).collect();
+        }
+    }
+
+    complete_path(base, input)
+}
+
+// it copies a file, or a dir and its contents, to `dest`
+// `on_file` is called with the size of each copied file
+// symlinks are copied as symlinks on unix
+// it returns the total size of the copied files
+pub fn copy_recursive(src: &Path, dest: &Path, on_file: &mut dyn FnMut(u64)) -> Result<u64, io::Error> {
+    let metadata = fs::symlink_metadata(src)?;
+
+    #[cfg(unix)]
+    if metadata.file_type().is_symlink() {
+        std::os::unix::fs::symlink(fs::read_link(src)?, dest)?;
+        on_file(0);
+        return Ok(0);
+    }
+
+    if metadata.is_dir() {
+        fs::create_dir(dest)?;
+        let mut result = 0;
+
+        for entry in fs::read_dir(src)? {
+            let entry = entry?;
+            result += copy_recursive(&entry.path(), &dest.join(entry.file_name()), on_file)?;
+        }
+
+        Ok(result)
+    }
+
+    else {
+        let size = fs::copy(src, dest)?;
+        on_file(size);
+        Ok(size)
+    }
+}
+
+// number of files that `copy_recursive` would copy
+fn count_files(path: &Path) -> usize {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => match fs::read_dir(path) {
+            Ok(entries) => entries.filter_map(
+                |entry| entry.ok()
+            ).map(
+                |entry| count_files(&entry.path())
+            ).sum(),
+            Err(_) => 0,
+        },
+        Ok(_) => 1,
+        Err(_) => 0,
+    }
+}
+
+// it runs `cmd` in a shell, with `path` (quoted) as the last argument
+// the child inherits the terminal, and it waits for the child to exit
+// it returns the exit code, which is -1 if the child is killed by a signal
+pub fn run_shell_command(cmd: &str, path: Option<&str>, cwd: &str) -> Result<i32, String> {
+    let cmd = match path {
+        Some(path) => format!("{cmd} {}", shell_quote(path)),
+        None => cmd.to_string(),
+    };
+
+    #[cfg(unix)]
+    let mut command = std::process::Command::new("sh");
+
+    #[cfg(unix)]
+    command.arg("-c");
+
+    #[cfg(not(unix))]
+    let mut command = std::process::Command::new("cmd");
+
+    #[cfg(not(unix))]
+    command.arg("/C");
+
+    // `read_command` has already left the raw mode, but let's make sure
+    let _ = crossterm::terminal::disable_raw_mode();
+
+    let status = command.arg(&cmd).current_dir(cwd).status().map_err(|e| e.to_string())?;
+
+    // the output would be cleared by the next screen
+    print!("\npress Enter to continue");
+    let _ = io::stdout().flush();
+    let _ = io::stdin().read_line(&mut String::new());
+
+    Ok(status.code().unwrap_or(-1))
+}
+
+#[cfg(target_os = "macos")]
+const DEFAULT_FILE_MANAGER: &str = "open";
+
+#[cfg(windows)]
+const DEFAULT_FILE_MANAGER: &str = "explorer.exe";
+
+#[cfg(not(any(target_os = "macos", windows)))]
+const DEFAULT_FILE_MANAGER: &str = "nautilus";
+
+// it doesn't wait for the file manager to exit
+pub fn open_in_file_manager(path: &str, file_manager: Option<&str>) -> Result<(), String> {
+    let file_manager = file_manager.unwrap_or(DEFAULT_FILE_MANAGER);
+
+    match std::process::Command::new(file_manager).arg(path).spawn() {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("failed to run {file_manager:?}: {e}")),
+    }
+}
+
+// `exited with 0` or `exited with 1 (error)`
+pub fn format_exit_code(result: Result<i32, String>) -> String {
+    match result {
+        Ok(0) => String::from("exited with 0"),
+        Ok(code) => format!("exited with {code} (error)"),
+        Err(e) => format!("failed to run the command: {e}"),
+    }
+}
+
+// opens the n-th top-level file of the current dir, in the order that `print_dir` shows them
+// `n` is the index that the index column shows, so the first file is `0`
+// a dir is entered, and the others are opened by the main loop, depending on their types
+pub fn goto_nth_file(state: &mut AppState, n: usize) {
+    if n > MAX_GOTO_INDEX {
+        state.print_dir_config.alert = format!("index must be between 0 and {MAX_GOTO_INDEX}");
+        return;
+    }
+
+    let uid = get_visible_children(state.curr_instance, &state.print_dir_config).get(n).map(|child| child.uid);
+
+    match uid {
+        Some(uid) => {
+            state.set_curr_uid(uid);
+            state.print_dir_config.offset = 0;
+        },
+        None => {
+            state.print_dir_config.alert = format!("there's no file at {n}");
+        },
+    }
+}
+
+fn parse_number(args: &str) -> usize {
+    parse_int_from(&args.chars().collect::<Vec<_>>()) as usize
+}
+
+// the margin is shared by all the viewers
+// it returns a message for the alert
+fn set_column_margin(state: &mut AppState, n: usize) -> String {
+    if n > MAX_COLUMN_MARGIN {
+        format!("margin must be at most {MAX_COLUMN_MARGIN}")
+    }
+
+    else {
+        state.print_dir_config.column_margin = n;
+        state.print_file_config.column_margin = n;
+        state.print_link_config.column_margin = n;
+        format!("column margin: {n}")
+    }
+}
+
+// the file viewer and the link viewer share `file_commands`
+// the alert goes to the viewer that's showing the current file
+fn file_view_alert(state: &mut AppState) -> &mut String {
+    if state.curr_instance.file_type == FileType::Symlink {
+        &mut state.print_link_config.alert
+    }
+
+    else {
+        &mut state.print_file_config.alert
+    }
+}
+
+// `;` commands of the dir viewer
+pub fn dir_commands() -> CommandTable {
+    let mut result: CommandTable = HashMap::new();
+
+    // moves the offset
+    for (name, n) in [("jj", 10), ("jjj", 100)] {
+        result.insert(name, command(CommandArgs::Nothing, move |state, _| {
+            state.print_dir_config.offset += n;
+        }));
+    }
+
+    for (name, n) in [("kk", 10), ("kkk", 100)] {
+        result.insert(name, command(CommandArgs::Nothing, move |state, _| {
+            state.print_dir_config.offset = state.print_dir_config.offset.max(n) - n;
+        }));
+    }
+
+    result.insert("j", command(CommandArgs::OptionalNumber, |state, args| {
+        let n = if args.is_empty() { 1 } else { parse_number(args) };
+        state.print_dir_config.offset += n;
+    }));
+
+    result.insert("k", command(CommandArgs::OptionalNumber, |state, args| {
+        let n = if args.is_empty() { 1 } else { parse_number(args) };
+        state.print_dir_config.offset = state.print_dir_config.offset.max(n) - n;
+    }));
+
+    // `;<N>` sets the offset, and `;n<N>` goes to the file
+    // FIXME: an error with file viewer -> try `;100` when there's less than 100 files
+    result.insert("", command(CommandArgs::Number, |state, args| {
+        state.print_dir_config.offset = parse_number(args);
+    }));
+
+    // `;m<N>` sets the margin between columns
+    // `;m <name>` creates a dir, and `;m a/b/c` creates the nested dirs
+    // the cursor moves to the new dir
+    result.insert("m", command(CommandArgs::Text, |state, args| {
+        if CommandArgs::Number.accepts(args) {
+            state.print_dir_config.alert = set_column_margin(state, parse_number(args));
+            return;
+        }
+
+        let name = args.trim();
+
+        if name.is_empty() || !args.starts_with(char::is_whitespace) {
+            state.print_dir_config.alert = String::from("usage: ;m<N> or ;m <name>");
+            return;
+        }
+
+        let curr_path = match try_get_path_by_uid(state.curr_uid) {
+            Some(path) => PathBuf::from(path),
+            None => {
+                state.print_dir_config.alert = format!("get_path_by_uid({}) has failed", state.curr_uid);
+                return;
+            },
+        };
+        if Path::new(name).is_absolute() {
+            state.print_dir_config.alert = String::from("the name must be relative to the current dir");
+            return;
+        }
+
+        let path = curr_path.join(name);
+
+        if path.symlink_metadata().is_ok() {
+            state.print_dir_config.alert = String::from("directory already exists");
+            return;
+        }
+
+        if let Err(e) = fs::create_dir_all(&path) {
+            state.print_dir_config.alert = e.to_string();
+            return;
+        }
+
+        // only the first component is a child of the current dir
+        let child_path = match Path::new(name).components().next() {
+            Some(component) => curr_path.join(component),
+            None => path,
+        };
+        let child_uid = Uid::from_path(&child_path.to_string_lossy());
+
+        // if the children are not read yet, they'll be read with the new dir
+        if let Some(children) = &state.curr_instance.children {
+            if !children.contains(&child_uid) {
+                File::new_from_path_buf(child_path, None, Some(state.curr_uid));
+                state.curr_instance.children.as_mut().unwrap().push(child_uid);
+            }
+        }
+
+        if let Some(index) = get_visible_children(state.curr_instance, &state.print_dir_config).iter().position(|f| f.uid == child_uid) {
+            state.print_dir_config.offset = index;
+        }
+
+        state.print_dir_config.alert = format!("created {name}");
+    }));
+
+    // goes to the file at the index of the index column, see `goto_nth_file`
+    result.insert("n", command(CommandArgs::Number, |state, args| {
+        goto_nth_file(state, parse_number(args));
+    }));
+
+    // sets how deep the contents of the children are shown
+    result.insert("L", command(CommandArgs::Number, |state, args| {
+        let n = parse_number(args);
+
+        if n > MAX_NESTED_LEVEL {
+            state.print_dir_config.alert = format!("nested level must be at most {MAX_NESTED_LEVEL}");
+        }
+
+        else {
+            state.print_dir_config.max_nested_level = n;
+            state.print_dir_config.alert = format!("nested level: {n}");
+        }
+    }));
+
+    // goes to the N-th component of the breadcrumb (1-based, from the left)
+    result.insert("b", command(CommandArgs::Number, |state, args| {
+        let n = parse_number(args);
+        let breadcrumb = get_breadcrumb(state.curr_uid);
+
+        if n == 0 || n > breadcrumb.len() {
+            state.print_dir_config.alert = format!("breadcrumb has {} components", breadcrumb.len());
+        }
+
+        else if breadcrumb[n - 1] != state.curr_uid {
+            state.set_curr_uid(breadcrumb[n - 1]);
+            state.print_dir_config.offset = 0;
+        }
+    }));
+
+    // each file gets a second row with more metadata
+    result.insert("v", command(CommandArgs::Nothing, |state, _| {
+        state.print_dir_config.verbose = !state.print_dir_config.verbose;
+        state.print_dir_config.alert = format!("verbose {}", if state.print_dir_config.verbose { "on" } else { "off" });
+    }));
+
+    // shows the absolute paths in the name column
+    result.insert("p", command(CommandArgs::Nothing, |state, _| {
+        state.print_dir_config.show_full_path = !state.print_dir_config.show_full_path;
+        state.print_dir_config.offset = 0;
+        state.print_dir_config.alert = format!("full path {}", if state.print_dir_config.show_full_path { "on" } else { "off" });
+    }));
+
+    // toggles the permissions column between `rwxr-xr--` and `754`
+    // it adds the column if there's none
+    result.insert("P", command(CommandArgs::Nothing, |state, _| {
+        let mut format = None;
+
+        for col in state.print_dir_config.columns.iter_mut() {
+            if let ColumnKind::Permissions(f) = col {
+                *f = f.toggle();
+                format = Some(*f);
+            }
+        }
+
+        if format.is_none() {
+            state.print_dir_config.columns.push(ColumnKind::Permissions(PermissionsFormat::Symbolic));
+            format = Some(PermissionsFormat::Symbolic);
+        }
+
+        state.print_dir_config.alert = format!("permissions: {}", format.unwrap());
+    }));
+
+    // shows only the dirs, as a deep tree
+    // the entry count column counts the files that are hidden by the tree
+    result.insert("T", command(CommandArgs::Nothing, |state, _| {
+        let config = &mut state.print_dir_config;
+        config.tree_mode = !config.tree_mode;
+        config.offset = 0;
+
+        if config.tree_mode {
+            config.columns.push(ColumnKind::EntryCount);
+        }
+
+        else {
+            config.columns.retain(|col| !matches!(col, ColumnKind::EntryCount));
+        }
+
+        config.alert = format!("tree mode: {}", if config.tree_mode { "on" } else { "off" });
+    }));
+
+    // goes to the next (or previous) dir in the parent dir
+    for (name, forward) in [(">", true), ("<", false)] {
+        result.insert(name, command(CommandArgs::Nothing, move |state, _| {
+            match get_adjacent_sibling(state.curr_instance, &state.print_dir_config, forward) {
+                Some(uid) => {
+                    state.set_curr_uid(uid);
+                    state.print_dir_config.offset = 0;
+                },
+                None => {
+                    state.print_dir_config.alert = format!("no {} file", if forward { "next" } else { "previous" });
+                },
+            }
+        }));
+    }
+
+    // `;tag <color>` tags the file at the cursor, and `;tag` removes the tag
+    result.insert("tag", command(CommandArgs::Text, |state, args| {
+        let color = args.trim();
+        let color = if color.is_empty() { Ok(None) } else { color.parse::<TagColor>().map(Some) };
+        let file = get_visible_children(state.curr_instance, &state.print_dir_config).get(state.print_dir_config.offset).map(|f| f.uid);
+
+        match (color, file.and_then(try_get_path_by_uid)) {
+            (Ok(color), Some(path)) => match set_tag(path, color) {
+                Ok(()) => {
+                    if !state.print_dir_config.columns.iter().any(|col| matches!(col, ColumnKind::Color)) {
+                        state.print_dir_config.columns.push(ColumnKind::Color);
+                    }
+
+                    state.print_dir_config.alert = match color {
+                        Some(color) => format!("tagged {path:?} {color}"),
+                        None => format!("removed the tag of {path:?}"),
+                    };
+                },
+                Err(e) => {
+                    state.print_dir_config.alert = format!("failed to save the tags: {e}");
+                },
+            },
+            (Err(e), _) => {
+                state.print_dir_config.alert = e;
+            },
+            (_, None) => {
+                state.print_dir_config.alert = String::from("no file at the cursor");
+            },
+        }
+    }));
+
+    // `;x <key> <value>` sets the xattr of the file at the cursor, and shows the column of the key
+    // `;x <key>` only shows the column
+    result.insert("x", command(CommandArgs::Text, |state, args| {
+        let (key, value) = match args.trim().split_once(' ') {
+            Some((key, value)) => (key, Some(value.trim())),
+            None => (args.trim(), None),
+        };
+
+        if key.is_empty() {
+            state.print_dir_config.alert = String::from("usage: ;x <key> <value>");
+            return;
+        }
+
+        if let Some(value) = value {
+            let file = get_visible_children(state.curr_instance, &state.print_dir_config).get(state.print_dir_config.offset).map(|f| f.uid);
+
+            match file.and_then(try_get_path_by_uid) {
+                Some(path) => match set_xattr(path, key, value) {
+                    Ok(()) => {
+                        state.print_dir_config.alert = format!("{key}={value:?}");
+                    },
+                    Err(e) => {
+                        state.print_dir_config.alert = format!("failed to set {key:?}: {e}");
+                        return;
+                    },
+                },
+                None => {
+                    state.print_dir_config.alert = String::from("no file at the cursor");
+                    return;
+                },
+            }
+        }
+
+        if !state.print_dir_config.columns.iter().any(|col| matches!(col, ColumnKind::CustomTag(k) if *k == key)) {
+            state.print_dir_config.columns.push(ColumnKind::CustomTag(intern_xattr_key(key)));
+        }
+    }));
+
+    // `;X <key>` removes the xattr of the file at the cursor
+    result.insert("X", command(CommandArgs::Text, |state, args| {
+        let key = args.trim();
+
+        if key.is_empty() {
+            state.print_dir_config.alert = String::from("usage: ;X <key>");
+            return;
+        }
+
+        let file = get_visible_children(state.curr_instance, &state.print_dir_config).get(state.print_dir_config.offset).map(|f| f.uid);
+
+        state.print_dir_config.alert = match file.and_then(try_get_path_by_uid) {
+            Some(path) => match remove_xattr(path, key) {
+                Ok(()) => format!("removed {key:?}"),
+                Err(e) => format!("failed to remove {key:?}: {e}"),
+            },
+            None => String::from("no file at the cursor"),
+        };
+    }));
+
+    // changes the sort column
+    for (name, sort_by) in [
+        ("sn", ColumnKind::Name),
+        ("ss", ColumnKind::Size),
+        ("sS", ColumnKind::TotalSize),
+        ("sm", ColumnKind::Modified),
+        ("st", ColumnKind::FileType),
+        ("se", ColumnKind::FileExt),
+    ] {
+        result.insert(name, command(CommandArgs::Nothing, move |state, _| {
+            state.print_dir_config.sort_by = sort_by;
+            state.print_dir_config.offset = 0;
+            state.print_dir_config.alert = format!("sorted by {}", sort_by.header_string());
+        }));
+    }
+
+    // shortcuts of the above, and pressing the same one again reverses the order
+    // `;A` sorts by the access time, which is `last opened` on macOS
+    for (name, sort_by) in [
+        ("A", ColumnKind::LastOpened),
+        ("M", ColumnKind::Modified),
+        ("N", ColumnKind::Name),
+        ("Z", ColumnKind::Size),
+    ] {
+        result.insert(name, command(CommandArgs::Nothing, move |state, _| {
+            if state.print_dir_config.sort_by == sort_by {
+                state.print_dir_config.sort_reverse = !state.print_dir_config.sort_reverse;
+            }
+
+            else {
+                state.print_dir_config.sort_by = sort_by;
+                state.print_dir_config.sort_reverse = false;
+            }
+
+            state.print_dir_config.offset = 0;
+            state.print_dir_config.alert = format!(
+                "sorted by {}{}",
+                sort_by.header_string(),
+                if state.print_dir_config.sort_reverse { " (reversed)" } else { "" },
+            );
+        }));
+    }
+
+    result.insert("sr", command(CommandArgs::Nothing, |state, _| {
+        state.print_dir_config.sort_reverse = !state.print_dir_config.sort_reverse;
+        state.print_dir_config.offset = 0;
+        state.print_dir_config.alert = format!(
+            "sorted by {}{}",
+            state.print_dir_config.sort_by.header_string(),
+            if state.print_dir_config.sort_reverse { " (reversed)" } else { "" },
+        );
+    }));
+
+    // `;h` toggles the hidden files, and saves it as the default
+    // `;H` toggles them only for this session
+    for (name, save) in [("h", true), ("H", false)] {
+        result.insert(name, command(CommandArgs::Nothing, move |state, _| {
+            let show = !state.print_dir_config.show_hidden_files;
+            state.print_dir_config.show_hidden_files = show;
+            state.print_dir_config.offset = 0;
+            state.print_dir_config.alert = String::from(if show { "showing hidden files" } else { "hiding hidden files" });
+
+            if save {
+                if let Err(e) = save_preference("show_hidden_files", toml::Value::Boolean(show)) {
+                    state.print_dir_config.alert = format!("{}, but failed to save it: {e}", state.print_dir_config.alert);
+                }
+            }
+        }));
+    }
+
+    // `;f` filters the files by name, updating the view as the user types
+    // Enter keeps the filter, and Escape (or an empty input) clears it
+    result.insert("f", command(CommandArgs::Nothing, |state, _| {
+        let curr_uid = state.curr_uid;
+        let config = &mut state.print_dir_config;
+
+        let input = read_line_live("filter: ", &mut |input| {
+            config.reset_alert();
+            config.offset = 0;
+
+            if input.is_empty() {
+                config.filter = None;
+            }
+
+            else {
+                match Regex::new(input) {
+                    Ok(re) => {
+                        config.filter = Some(re);
+                    },
+                    Err(_) => {
+                        config.alert = format!("invalid regex: {input:?}");
+                    },
+                }
+            }
+
+            unsafe { IS_MASTER_WORKING = true; }
+            print_dir(curr_uid, config);
+            flip_buffer(true);
+            unsafe { IS_MASTER_WORKING = false; }
+        });
+
+        config.offset = 0;
+
+        match input.as_deref().map(Regex::new) {
+            Some(Ok(re)) if !re.as_str().is_empty() => {
+                config.alert = format!("filter: {}", re.as_str());
+                config.filter = Some(re);
+            },
+            Some(Err(_)) => {
+                config.filter = None;
+                config.alert = format!("invalid regex: {:?}", input.unwrap());
+            },
+            _ => {
+                config.filter = None;
+                config.alert = String::from("filter cleared");
+            },
+        }
+    }));
+
+    // `;! <cmd>` runs a shell command in the current dir
+    result.insert("!", command(CommandArgs::Text, |state, args| {
+        let cmd = args.trim();
+
+        if cmd.is_empty() {
+            state.print_dir_config.alert = String::from("usage: ;! <command>");
+            return;
+        }
+
+        state.print_dir_config.alert = match try_get_path_by_uid(state.curr_uid) {
+            Some(cwd) => format_exit_code(run_shell_command(cmd, None, cwd)),
+            None => format!("get_path_by_uid({}) has failed", state.curr_uid),
+        };
+
+        // the command might have changed the dir
+        if state.navigation_mode == NavigationMode::FileSystem {
+            state.curr_instance.refresh_children();
+        }
+    }));
+
+    // shows all the metadata of the file at the cursor, until the user presses a key
+    result.insert("=", command(CommandArgs::Nothing, |state, _| {
+        match get_visible_children(state.curr_instance, &state.print_dir_config).get(state.print_dir_config.offset).map(|f| f.uid) {
+            Some(uid) => {
+                unsafe { IS_MASTER_WORKING = true; }
+                print_stat(uid, &state.print_dir_config);
+                flip_buffer(true);
+                unsafe { IS_MASTER_WORKING = false; }
+
+                wait_for_key();
+            },
+            None => {
+                state.print_dir_config.alert = String::from("no file at the cursor");
+            },
+        }
+    }));
+
+    // lists the files with the same contents, among the visible files (and the descendants with `;F`)
+    // files with a unique size are not hashed at all
+    result.insert("D", command(CommandArgs::Nothing, |state, _| {
+        // workers can't read the members of an archive
+        if state.navigation_mode != NavigationMode::FileSystem {
+            state.print_dir_config.alert = String::from("cannot find duplicates inside an archive");
+            return;
+        }
+
+        let mut files_by_size: HashMap<u64, Vec<Uid>> = HashMap::new();
+
+        for file in get_visible_children(state.curr_instance, &state.print_dir_config).iter() {
+            // empty files are all the same, and that's not interesting
+            if file.is_file() && file.size > 0 {
+                files_by_size.entry(file.size).or_default().push(file.uid);
+            }
+        }
+
+        let candidates = files_by_size.into_values().filter(|uids| uids.len() > 1).flatten().collect::<Vec<_>>();
+
+        for uid in candidates.iter() {
+            if get_file_by_uid(*uid).map(|file| file.checksum.is_none()).unwrap_or(false) {
+                send_work(WorkItem::ComputeChecksum(*uid));
+            }
+        }
+
+        unsafe { IS_MASTER_WORKING = true; }
+        let mut last_update = Instant::now();
+
+        // any key cancels it, and `is_key_pressed` needs the raw mode
+        let _ = crossterm::terminal::enable_raw_mode();
+
+        let is_cancelled = loop {
+            collect_work_results();
+            let pending = candidates.iter().filter(|uid| is_work_pending(WorkItem::ComputeChecksum(**uid))).count();
+
+            if pending == 0 {
+                break false;
+            }
+
+            if is_key_pressed() {
+                break true;
+            }
+
+            if last_update.elapsed() >= CHECKSUM_PROGRESS_INTERVAL {
+                last_update = Instant::now();
+                print!("\rcomputed checksums of {} of {} files (press any key to cancel)", candidates.len() - pending, candidates.len());
+                let _ = io::stdout().flush();
+            }
+
+            std::thread::sleep(Duration::from_millis(20));
+        };
+
+        let _ = crossterm::terminal::disable_raw_mode();
+        unsafe { IS_MASTER_WORKING = false; }
+
+        // the workers finish the pending items anyway, and the checksums are kept for the next `;D`
+        if is_cancelled {
+            state.print_dir_config.alert = String::from("cancelled finding duplicates");
+            return;
+        }
+        let mut files_by_contents: HashMap<(u64, [u8; 32]), Vec<Uid>> = HashMap::new();
+
+        for uid in candidates.iter() {
+            // unreadable files don't have checksums
+            if let Some((size, Some(checksum))) = get_file_by_uid(*uid).map(|file| (file.size, file.checksum)) {
+                files_by_contents.entry((size, checksum)).or_default().push(*uid);
+            }
+        }
+
+        // the biggest waste comes first
+        let mut groups = files_by_contents.into_iter().filter(
+            |(_, uids)| uids.len() > 1
+        ).map(
+            |((size, checksum), uids)| (size, checksum, uids)
+        ).collect::<Vec<DuplicateGroup>>();
+        groups.sort_by_key(|(size, _, uids)| Reverse(*size * (uids.len() as u64 - 1)));
+
+        if groups.is_empty() {
+            state.print_dir_config.alert = String::from("no duplicates");
+        }
+
+        else {
+            print_duplicates(state.curr_uid, &groups, &state.print_dir_config);
+            flip_buffer(true);
+            wait_for_key();
+        }
+    }));
+
+    // opens the current dir in a graphical file manager
+    result.insert("w", command(CommandArgs::Nothing, |state, _| {
+        state.print_dir_config.alert = match try_get_path_by_uid(state.curr_uid) {
+            Some(path) => match open_in_file_manager(path, state.print_dir_config.external_file_manager.as_deref()) {
+                Ok(()) => String::from("opened in file manager"),
+                Err(e) => e,
+            },
+            None => format!("get_path_by_uid({}) has failed", state.curr_uid),
+        };
+    }));
+
+    // toggles the elapsed time
+    result.insert("t", command(CommandArgs::Nothing, |state, _| {
+        state.print_dir_config.show_elapsed_time = !state.print_dir_config.show_elapsed_time;
+    }));
+
+    // searches the contents of the descendants, like `grep -r`
+    // `;G` without a pattern clears the results
+    result.insert("G", command(CommandArgs::Text, |state, args| {
+        let pattern = args.trim();
+
+        if pattern.is_empty() {
+            clear_grep_results(&mut state.print_dir_config);
+        }
+
+        else {
+            match Regex::new(pattern) {
+                Ok(re) => {
+                    let results = grep_dir(state.curr_uid, &re, state.print_dir_config.show_hidden_files);
+                    set_grep_results(&results);
+
+                    if !state.print_dir_config.columns.iter().any(|col| matches!(col, ColumnKind::MatchCount)) {
+                        let name_index = state.print_dir_config.columns.iter().position(|col| matches!(col, ColumnKind::Name)).unwrap_or(0);
+                        state.print_dir_config.columns.insert(name_index + 1, ColumnKind::MatchCount);
+                    }
+
+                    state.print_dir_config.grep = Some((state.curr_uid, re));
+                    state.print_dir_config.offset = 0;
+                    state.print_dir_config.alert = format!("{} files match {pattern:?}", results.len());
+                },
+                Err(_) => {
+                    state.print_dir_config.alert = format!("invalid regex: {pattern:?}");
+                },
+            }
+        }
+    }));
+
+    // toggles the error log panel
+    result.insert("E", command(CommandArgs::Nothing, |state, _| {
+        state.print_dir_config.show_error_log = !state.print_dir_config.show_error_log;
+    }));
+
+    // renames the file at the cursor
+    result.insert("r", command(CommandArgs::Nothing, |state, _| {
+        let file = get_visible_children(state.curr_instance, &state.print_dir_config).get(state.print_dir_config.offset).map(|f| f.uid);
+
+        match file.and_then(get_file_by_uid) {
+            Some(file) => {
+                let new_name = read_line_with_default("rename: ", &file.name);
+
+                // an empty name cancels it
+                if new_name.is_empty() || new_name == file.name {
+                    return;
+                }
+
+                match file.rename(&new_name) {
+                    Ok(()) => {
+                        state.curr_instance.refresh_children();
+                        state.print_dir_config.alert = format!("renamed to {new_name:?}");
+                    },
+                    Err(e) => {
+                        state.print_dir_config.alert = e;
+                    },
+                }
+            },
+            None => {
+                state.print_dir_config.alert = String::from("no file at the cursor");
+            },
+        }
+    }));
+
+    // `;c <dest>` copies the file at the cursor to the `dest` dir
+    result.insert("c", command(CommandArgs::Text, |state, args| {
+        let dest = args.trim();
+
+        if dest.is_empty() {
+            state.print_dir_config.alert = String::from("usage: ;c <dest>");
+            return;
+        }
+
+        let curr_path = match try_get_path_by_uid(state.curr_uid) {
+            Some(path) => PathBuf::from(path),
+            None => {
+                state.print_dir_config.alert = format!("get_path_by_uid({}) has failed", state.curr_uid);
+                return;
+            },
+        };
+        let dest = match curr_path.join(dest).canonicalize() {
+            Ok(dest) if dest.is_dir() => dest,
+            _ => {
+                state.print_dir_config.alert = format!("{dest:?} is not a dir");
+                return;
+            },
+        };
+
+        let files = match get_visible_children(state.curr_instance, &state.print_dir_config).get(state.print_dir_config.offset) {
+            Some(file) => vec![file.uid],
+            None => {
+                state.print_dir_config.alert = String::from("no file at the cursor");
+                return;
+            },
+        };
+        let srcs = files.iter().filter_map(
+            |uid| try_get_path_by_uid(*uid).map(PathBuf::from)
+        ).collect::<Vec<_>>();
+
+        let total_files = srcs.iter().map(|src| count_files(src)).sum::<usize>();
+        let mut copied_files = 0;
+        let mut copied_bytes = 0;
+        let mut last_update = Instant::now();
+        let mut errors = vec![];
+
+        for src in srcs.iter() {
+            let name = match src.file_name() {
+                Some(name) => name,
+                None => {
+                    continue;
+                },
+            };
+            let file_dest = dest.join(name);
+
+            // a dir cannot be copied into itself
+            if file_dest.starts_with(src) {
+                errors.push(format!("cannot copy {:?} into itself", src.to_string_lossy()));
+                continue;
+            }
+
+            if file_dest.symlink_metadata().is_ok() {
+                errors.push(format!("{:?} already exists", file_dest.to_string_lossy()));
+                continue;
+            }
+
+            let result = copy_recursive(src, &file_dest, &mut |size| {
+                copied_files += 1;
+                copied_bytes += size;
+
+                if last_update.elapsed() >= COPY_PROGRESS_INTERVAL {
+                    last_update = Instant::now();
+                    print!("\rcopied {copied_files} of {total_files} files ({:.1} MiB)", copied_bytes as f64 / 1048576.0);
+                    let _ = io::stdout().flush();
+                }
+            });
+
+            if let Err(e) = result {
+                errors.push(format!("failed to copy {:?}: {e}", src.to_string_lossy()));
+            }
+        }
+
+        // the copies show up if `dest` is already loaded
+        if let Some(dest_dir) = get_uid_by_path(&dest.to_string_lossy()).and_then(get_file_by_uid) {
+            dest_dir.refresh_children();
+        }
+
+        state.print_dir_config.alert = match errors.first() {
+            Some(e) => format!("copied {copied_files} files, {} errors: {e}", errors.len()),
+            None => format!("copied {copied_files} files"),
+        };
+    }));
+
+    // deletes the file at the cursor
+    result.insert("d", command(CommandArgs::Nothing, |state, _| {
+        if state.navigation_mode != NavigationMode::FileSystem {
+            state.print_dir_config.alert = String::from("cannot delete a file in an archive");
+            return;
+        }
+
+        let file = get_visible_children(state.curr_instance, &state.print_dir_config).get(state.print_dir_config.offset).map(|f| f.uid);
+
+        match file.and_then(get_file_by_uid) {
+            Some(file) => {
+                if state.print_dir_config.require_delete_confirmation {
+                    let answer = read_line_with_default(&format!("delete {}? [y/N] ", file.name), "");
+
+                    if !answer.trim().eq_ignore_ascii_case("y") {
+                        return;
+                    }
+                }
+
+                match file.delete() {
+                    Ok(()) => {
+                        state.print_dir_config.alert = format!("deleted {:?}", file.name);
+                        unregister_file(file.uid);
+                    },
+                    Err(e) => {
+                        state.print_dir_config.alert = e;
+                    },
+                }
+            },
+            None => {
+                state.print_dir_config.alert = String::from("no file at the cursor");
+            },
+        }
+    }));
+
+    // toggles the flat list of all the descendants
+    result.insert("F", command(CommandArgs::Nothing, |state, _| {
+        state.print_dir_config.recursive = !state.print_dir_config.recursive;
+        state.print_dir_config.offset = 0;
+    }));
+
+    // reads `config.toml` again, so that the user doesn't have to restart after editing it
+    result.insert("R", command(CommandArgs::Nothing, |state, _| {
+        state.print_dir_config.alert = match reload_preferences(&mut state.print_dir_config) {
+            Ok(()) => {
+                state.print_dir_config.adjust_output_dimension();
+                state.print_file_config.adjust_output_dimension();
+                state.print_link_config.adjust_output_dimension();
+                String::from("config reloaded")
+            },
+            Err(e) => format!("failed to reload the config: {e}"),
+        };
+    }));
+
+    // enters the zip archive at the cursor
+    result.insert("z", command(CommandArgs::Nothing, |state, _| {
+        let archive = get_visible_children(state.curr_instance, &state.print_dir_config).get(state.print_dir_config.offset).filter(
+            |f| f.is_file() && matches!(&f.file_ext, Some(ext) if ext.eq_ignore_ascii_case("zip"))
+        ).map(
+            |f| f.uid
+        );
+
+        match archive {
+            Some(archive) => match enter_archive(archive) {
+                Ok(root) => {
+                    state.navigation_mode = NavigationMode::Archive { archive, root };
+                    state.set_curr_uid(root);
+                    state.print_dir_config.offset = 0;
+                },
+                Err(e) => {
+                    state.print_dir_config.alert = e;
+                },
+            },
+            None => {
+                state.print_dir_config.alert = String::from("not a zip archive");
+            },
+        }
+    }));
+
+    result
+}
+
+// `;` commands of the file viewer and the link viewer
+// the main loop resets the viewer if a command changes `curr_uid`
+pub fn file_commands() -> CommandTable {
+    let mut result: CommandTable = HashMap::new();
+
+    // sets the margin between columns
+    result.insert("m", command(CommandArgs::Number, |state, args| {
+        *file_view_alert(state) = set_column_margin(state, parse_number(args));
+    }));
+
+    // cycles through the encodings of the text viewer
+    result.insert("enc", command(CommandArgs::Nothing, |state, _| {
+        state.print_file_config.encoding = state.print_file_config.encoding.next();
+        state.print_file_config.alert = format!("encoding: {}", state.print_file_config.encoding);
+    }));
+
+    // `;H <r> <g> <b>` sets the color of the search highlights
+    // `;H` toggles the background of the highlighted lines
+    result.insert("H", command(CommandArgs::Text, |state, args| {
+        let rgb = args.split_whitespace().map(|n| n.parse::<u8>()).collect::<Vec<_>>();
+
+        if rgb.is_empty() {
+            state.print_file_config.highlight_background = !state.print_file_config.highlight_background;
+            state.print_file_config.alert = format!("highlight background: {}", if state.print_file_config.highlight_background { "on" } else { "off" });
+        }
+
+        else if let [Ok(r), Ok(g), Ok(b)] = rgb[..] {
+            state.print_file_config.highlight_color = Color::TrueColor { r, g, b };
+            state.print_file_config.alert = format!("highlight color: ({r}, {g}, {b})");
+        }
+
+        else {
+            state.print_file_config.alert = String::from("usage: ;H <r> <g> <b>");
+        }
+    }));
+
+    // `;! <cmd>` runs a shell command with the path of the file as the last argument
+    result.insert("!", command(CommandArgs::Text, |state, args| {
+        let cmd = args.trim();
+
+        let alert = if cmd.is_empty() {
+            String::from("usage: ;! <command>")
+        } else {
+            let cwd = try_get_path_by_uid(state.curr_instance.get_parent_uid());
+
+            match (try_get_path_by_uid(state.curr_uid), cwd) {
+                (Some(path), Some(cwd)) => format_exit_code(run_shell_command(cmd, Some(path), cwd)),
+                _ => format!("get_path_by_uid({}) has failed", state.curr_uid),
+            }
+        };
+
+        *file_view_alert(state) = alert;
+    }));
+
+    // `;W <N>` wraps lines at N columns, and `;W` wraps at the terminal width
+    result.insert("W", command(CommandArgs::Text, |state, args| {
+        let args = args.trim();
+
+        if !args.chars().all(|c| c.is_ascii_digit()) {
+            state.print_file_config.alert = String::from("usage: ;W <N>");
+            return;
+        }
+
+        state.print_file_config.wrap_lines = true;
+        state.print_file_config.wrap_column = if args.is_empty() { None } else { Some(parse_number(args).max(1)) };
+        state.print_file_config.alert = format_wrap_alert(&state.print_file_config);
+    }));
+
+    // toggles the tab width between 4 and 8
+    result.insert("T", command(CommandArgs::Nothing, |state, _| {
+        state.print_file_config.tab_width = if state.print_file_config.tab_width == 4 { 8 } else { 4 };
+        state.print_file_config.alert = format!("tab width: {}", state.print_file_config.tab_width);
+    }));
+
+    // follows the symlink to its target, which is opened like any other file
+    result.insert("l", command(CommandArgs::Nothing, |state, _| {
+        if state.curr_instance.file_type != FileType::Symlink {
+            *file_view_alert(state) = String::from("not a symlink");
+            return;
+        }
+
+        match follow_symlink(state.curr_uid) {
+            Ok(uid) => {
+                state.set_curr_uid(uid);
+            },
+            Err(e) => {
+                state.print_link_config.alert = e;
+            },
+        }
+    }));
+
+    // goes to the next (or previous) file in the parent dir
+    for (name, forward) in [(">", true), ("<", false)] {
+        result.insert(name, command(CommandArgs::Nothing, move |state, _| {
+            match get_adjacent_sibling(state.curr_instance, &state.print_dir_config, forward) {
+                Some(uid) => {
+                    state.set_curr_uid(uid);
+                },
+                None => {
+                    *file_view_alert(state) = format!("no {} file", if forward { "next" } else { "previous" });
+                },
+            }
+        }));
+    }
+
+    result
+}
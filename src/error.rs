@@ -0,0 +1,49 @@
+use crate::uid::Uid;
+use std::io;
+
+/// Errors that `print_error_message` renders.
+pub enum FileQueryError {
+    IoError(io::Error),
+
+    // `get_path_by_uid` has failed
+    PathNotFound(Uid),
+
+    // `get_file_by_uid` has failed
+    FileNotFound(Uid),
+
+    PermissionDenied(String),
+    Other(String),
+}
+
+impl FileQueryError {
+    /// It's shown in the first row of the error box.
+    pub fn kind(&self) -> String {
+        match self {
+            FileQueryError::IoError(e) => format!("io error ({:?})", e.kind()),
+            FileQueryError::PathNotFound(_) => String::from("path not found"),
+            FileQueryError::FileNotFound(_) => String::from("file not found"),
+            FileQueryError::PermissionDenied(_) => String::from("permission denied"),
+            FileQueryError::Other(_) => String::from("error"),
+        }
+    }
+
+    /// It's shown in the second row of the error box.
+    pub fn details(&self) -> String {
+        match self {
+            FileQueryError::IoError(e) => e.to_string(),
+            FileQueryError::PathNotFound(uid) => format!("get_path_by_uid({uid}) has failed"),
+            FileQueryError::FileNotFound(uid) => format!("get_file_by_uid({uid}) has failed"),
+            FileQueryError::PermissionDenied(s)
+            | FileQueryError::Other(s) => s.to_string(),
+        }
+    }
+}
+
+impl From<io::Error> for FileQueryError {
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::PermissionDenied => FileQueryError::PermissionDenied(e.to_string()),
+            _ => FileQueryError::IoError(e),
+        }
+    }
+}
@@ -1,21 +1,108 @@
 use crate::{FILES, PATHS};
-use crate::utils::{get_file_by_uid, get_path_by_uid};
+use crate::archive::{is_archive_member, read_archive_member};
+use crate::print::try_extract_utf8_text;
+use crate::print::ColumnKind;
+use crate::utils::{
+    error_log_push,
+    get_file_by_uid,
+    get_total_dir_size_fast,
+    get_uid_by_path,
+    is_network_file_system,
+    sort_files,
+    try_get_path_by_uid,
+};
 use crate::uid::Uid;
+use crate::worker::{send_work, WorkItem};
+use filetime::FileTime;
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
 use std::fmt;
 use std::fs;
 use std::io;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf, Prefix};
 use std::str::FromStr;
 use std::time::SystemTime;
 
 #[cfg(unix)]
-use std::os::unix::fs::PermissionsExt;
+use std::os::unix::fs::{FileExt, FileTypeExt, MetadataExt, PermissionsExt};
+
+#[cfg(not(unix))]
+use std::os::windows::fs::FileExt;
+
+// `File::get_descendants` doesn't read more dirs if `FILES` is bigger than this
+const MAX_FILES_FOR_DESCENDANTS: usize = 100_000;
+
+// `File::get_preview` reads this many bytes
+const PREVIEW_BYTES: usize = 256;
+
+// `File::get_is_executable` looks for a shebang in files with these extensions
+const SCRIPT_EXTENSIONS: [&str; 5] = ["py", "rb", "sh", "js", "pl"];
 
 #[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
 pub enum FileType {
     File,
     Dir,
     Symlink,
+
+    // character devices and block devices
+    #[cfg(unix)]
+    Device,
+
+    #[cfg(unix)]
+    Fifo,
+
+    #[cfg(unix)]
+    Socket,
+
+    // none of the above
+    #[cfg(unix)]
+    Unknown,
+}
+
+impl FileType {
+    pub fn from_metadata(metadata: &fs::Metadata) -> Self {
+        let file_type = metadata.file_type();
+
+        if file_type.is_symlink() {
+            FileType::Symlink
+        }
+
+        else if file_type.is_dir() {
+            FileType::Dir
+        }
+
+        else {
+            FileType::from_special_file_type(file_type)
+        }
+    }
+
+    #[cfg(unix)]
+    fn from_special_file_type(file_type: fs::FileType) -> Self {
+        if file_type.is_file() {
+            FileType::File
+        }
+
+        else if file_type.is_char_device() || file_type.is_block_device() {
+            FileType::Device
+        }
+
+        else if file_type.is_fifo() {
+            FileType::Fifo
+        }
+
+        else if file_type.is_socket() {
+            FileType::Socket
+        }
+
+        else {
+            FileType::Unknown
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn from_special_file_type(_: fs::FileType) -> Self {
+        FileType::File
+    }
 }
 
 impl fmt::Display for FileType {
@@ -26,6 +113,14 @@ impl fmt::Display for FileType {
                 FileType::File => "file",
                 FileType::Dir => "dir",
                 FileType::Symlink => "link",
+                #[cfg(unix)]
+                FileType::Device => "device",
+                #[cfg(unix)]
+                FileType::Fifo => "fifo",
+                #[cfg(unix)]
+                FileType::Socket => "socket",
+                #[cfg(unix)]
+                FileType::Unknown => "unknown",
             }
         )
     }
@@ -38,15 +133,85 @@ pub struct File {
     pub last_modified: SystemTime,
     pub size: u64,
     pub recursive_size: Option<u64>,  // if it's not calculated yet, it's None
+    pub depth: Option<usize>,  // see `get_depth`, if it's not calculated yet, it's None
+    pub checksum: Option<[u8; 32]>,  // if it's not calculated yet, it's None
+
+    // (lines, words, bytes), computed by workers
+    // `None`: not calculated yet, `Some(None)`: not a text file
+    pub wc: Option<Option<(u32, u32, u64)>>,
+
+    // the first line of a text file, or the first 4 bytes of a binary file
+    pub preview: Option<String>,  // if it's not calculated yet, it's None
     pub file_type: FileType,
     pub file_ext: Option<String>,
     pub children: Option<Vec<Uid>>,
 
     // TODO: it's always `false` on windows
+    // use `get_is_executable`, which also checks the shebang of scripts
     pub is_executable: bool,
+
+    // whether `get_is_executable` has read the shebang
+    pub shebang_checked: bool,
+
+    // (device, inode) and the number of hard links
+    // TODO: it's always `None` and 1 on windows
+    pub inode: Option<(u64, u64)>,
+    pub nlink: u64,
+
+    // some file systems don't record it
+    pub created_at: Option<FileTime>,
+
+    // access time on linux, which is not updated with `noatime`
+    // on macOS, it's `kMDItemLastUsedDate` of spotlight, which is computed by workers
+    pub last_opened: Option<SystemTime>,
+
+    // permission bits (`st_mode & 0o7777`)
+    // TODO: it's always `None` on windows
+    pub mode: Option<u32>,
+
+    // a dot-prefixed name, or `FILE_ATTRIBUTE_HIDDEN` on windows
+    // it's computed once, because `get_children` filters with it very often
+    pub is_hidden: bool,
+
+    // whether it's on a network file system (nfs, smb, ...)
+    // a dir knows it when its children are initialized, and the children inherit it from the dir
+    pub is_network_mount: bool,
+}
+
+#[cfg(windows)]
+fn has_hidden_attribute(metadata: &fs::Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+
+    metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0
 }
 
-// TODO: `File::new_from_XXX` generates different UID (and hence different instances) when called multiple times with the same path
+// unix doesn't have the attribute, the dot prefix is the only convention
+#[cfg(not(windows))]
+fn has_hidden_attribute(_: &fs::Metadata) -> bool {
+    false
+}
+
+// `Path::file_name` is `None` for the roots of windows paths, like `\\server\share` (UNC) and `\\?\C:\`
+// their prefix (without `\\?\`) is used as the name
+// it's always `None` on unix, where paths don't have a prefix
+fn get_prefix_name(path: &Path) -> Option<String> {
+    match path.components().next()? {
+        Component::Prefix(prefix) => match prefix.kind() {
+            Prefix::Verbatim(s)
+            | Prefix::DeviceNS(s) => s.to_str().map(|s| s.to_string()),
+            Prefix::VerbatimUNC(server, share)
+            | Prefix::UNC(server, share) => Some(format!(
+                "\\\\{}\\{}",
+                server.to_str()?,
+                share.to_str()?,
+            )),
+            Prefix::VerbatimDisk(disk)
+            | Prefix::Disk(disk) => Some(format!("{}:", disk as char)),
+        },
+        _ => None,
+    }
+}
 
 impl File {
     // it registers the instance to the cache, and only returns its uid
@@ -55,28 +220,25 @@ impl File {
             Some(s) => match s.to_str() {
                 Some(s) => s.to_string(),
                 None => {
-                    return File::from_error_msg(String::new());
+                    return File::from_error_msg(format!("{:?} is not a valid utf-8 name", s), &path.to_string_lossy());
                 },
             },
             None if uid == Some(Uid::ROOT) => String::new(),
-            None => {
-                return File::from_error_msg(String::new());
+            None => match get_prefix_name(&path) {
+                Some(name) => name,
+                None => {
+                    return File::from_error_msg(format!("{:?} does not have a name", path.to_string_lossy()), &path.to_string_lossy());
+                },
             },
         };
-        let (last_modified, size, file_type, is_executable) = match path.metadata() {
+        let (last_modified, size, file_type, is_executable, inode, nlink, created_at, last_opened, mode, hidden_attribute) = match path.metadata() {
             Ok(metadata) => {
-                let file_type = if metadata.is_symlink() {
-                    FileType::Symlink
-                } else if metadata.is_dir() {
-                    FileType::Dir
-                } else {
-                    FileType::File
-                };
+                let file_type = FileType::from_metadata(&metadata);
                 let size = metadata.len();
                 let last_modified = match metadata.modified() {
                     Ok(last_modified) => last_modified,
                     Err(e) => {
-                        return File::from_io_error(e);
+                        return File::from_io_error(e, &path.to_string_lossy());
                     },
                 };
 
@@ -86,10 +248,32 @@ impl File {
                 #[cfg(not(unix))]
                 let is_executable = false;
 
-                (last_modified, size, file_type, is_executable)
+                #[cfg(unix)]
+                let (inode, nlink) = (Some((metadata.dev(), metadata.ino())), metadata.nlink());
+
+                #[cfg(not(unix))]
+                let (inode, nlink) = (None, 1);
+
+                #[cfg(unix)]
+                let mode = Some(metadata.permissions().mode() & 0o7777);
+
+                #[cfg(not(unix))]
+                let mode = None;
+
+                // `Metadata::created` panics on some platforms
+                let created_at = FileTime::from_creation_time(&metadata);
+                let hidden_attribute = has_hidden_attribute(&metadata);
+
+                #[cfg(not(target_os = "macos"))]
+                let last_opened = metadata.accessed().ok();
+
+                #[cfg(target_os = "macos")]
+                let last_opened = None;
+
+                (last_modified, size, file_type, is_executable, inode, nlink, created_at, last_opened, mode, hidden_attribute)
             },
             Err(e) => {
-                return File::from_io_error(e);
+                return File::from_io_error(e, &path.to_string_lossy());
             },
         };
         let file_ext = match path.extension() {
@@ -99,24 +283,34 @@ impl File {
             },
             None => None,
         };
+        let is_hidden = hidden_attribute || name.starts_with('.');
 
         let result = File {
             parent,
-            uid: uid.unwrap_or_else(|| Uid::normal_file()),
+            uid: uid.unwrap_or_else(|| Uid::from_path_cached(&path.to_string_lossy())),
             name,
             last_modified,
             size,
             recursive_size: if file_type == FileType::File { Some(size) } else { None },
+            depth: None,
+            checksum: None,
+            wc: None,
+            preview: None,
             file_type,
             file_ext,
             children: None,
             is_executable,
+            shebang_checked: false,
+            inode,
+            nlink,
+            created_at,
+            last_opened,
+            mode,
+            is_hidden,
+            is_network_mount: false,
         };
 
-        let result_uid = result.uid;
-
-        let files = unsafe { FILES.as_mut().unwrap() };
-        files.insert(result_uid, result);
+        let result_uid = File::register(result);
 
         let paths = unsafe { PATHS.as_mut().unwrap() };
         paths.insert(result_uid, path.to_str().unwrap().to_string());
@@ -133,20 +327,14 @@ impl File {
 
     // it registers the instance to the cache, and only returns its uid
     pub fn new_from_dir_entry(dir_entry: fs::DirEntry, parent: Option<Uid>) -> Uid {
-        let (last_modified, size, file_type, is_executable) = match dir_entry.metadata() {
+        let (last_modified, size, file_type, is_executable, inode, nlink, created_at, last_opened, mode, hidden_attribute) = match dir_entry.metadata() {
             Ok(metadata) => {
-                let file_type = if metadata.is_symlink() {
-                    FileType::Symlink
-                } else if metadata.is_dir() {
-                    FileType::Dir
-                } else {
-                    FileType::File
-                };
+                let file_type = FileType::from_metadata(&metadata);
                 let size = metadata.len();
                 let last_modified = match metadata.modified() {
                     Ok(last_modified) => last_modified,
                     Err(e) => {
-                        return File::from_io_error(e);
+                        return File::from_io_error(e, &dir_entry.path().to_string_lossy());
                     },
                 };
 
@@ -156,16 +344,38 @@ impl File {
                 #[cfg(not(unix))]
                 let is_executable = false;
 
-                (last_modified, size, file_type, is_executable)
+                #[cfg(unix)]
+                let (inode, nlink) = (Some((metadata.dev(), metadata.ino())), metadata.nlink());
+
+                #[cfg(not(unix))]
+                let (inode, nlink) = (None, 1);
+
+                #[cfg(unix)]
+                let mode = Some(metadata.permissions().mode() & 0o7777);
+
+                #[cfg(not(unix))]
+                let mode = None;
+
+                // `Metadata::created` panics on some platforms
+                let created_at = FileTime::from_creation_time(&metadata);
+                let hidden_attribute = has_hidden_attribute(&metadata);
+
+                #[cfg(not(target_os = "macos"))]
+                let last_opened = metadata.accessed().ok();
+
+                #[cfg(target_os = "macos")]
+                let last_opened = None;
+
+                (last_modified, size, file_type, is_executable, inode, nlink, created_at, last_opened, mode, hidden_attribute)
             },
             Err(e) => {
-                return File::from_io_error(e);
+                return File::from_io_error(e, &dir_entry.path().to_string_lossy());
             },
         };
         let name = match dir_entry.file_name().to_str() {
             Some(s) => s.to_string(),
             None => {
-                return File::from_error_msg(String::new());
+                return File::from_error_msg(String::new(), &dir_entry.path().to_string_lossy());
             },
         };
         let file_ext = match dir_entry.path().extension() {
@@ -175,83 +385,110 @@ impl File {
             },
             None => None,
         };
+        let is_hidden = hidden_attribute || name.starts_with('.');
 
         let result = File {
             parent,
-            uid: Uid::normal_file(),
+            uid: Uid::from_path_cached(&dir_entry.path().to_string_lossy()),
             name,
             last_modified,
             size,
             recursive_size: if file_type == FileType::File { Some(size) } else { None },
+            depth: None,
+            checksum: None,
+            wc: None,
+            preview: None,
             file_type,
             file_ext,
             children: None,
             is_executable,
+            shebang_checked: false,
+            inode,
+            nlink,
+            created_at,
+            last_opened,
+            mode,
+            is_hidden,
+            is_network_mount: false,
         };
 
-        let result_uid = result.uid;
+        File::register(result)
+    }
 
+    // `new_from_XXX` registers the instance with this, and it returns the uid
+    // if the uid is already registered (e.g. the parent dir is read again), it updates the registered instance
+    // with the new metadata, but the instance keeps its parent and its caches (`children`, `recursive_size`, ...)
+    // the caches are dropped if the file has been modified since
+    fn register(mut file: File) -> Uid {
+        let uid = file.uid;
         let files = unsafe { FILES.as_mut().unwrap() };
-        files.insert(result_uid, result);
 
-        result_uid
+        match files.get_mut(&uid) {
+            Some(old) => {
+                let is_modified = old.file_type != file.file_type
+                    || old.last_modified != file.last_modified
+                    || old.size != file.size;
+
+                if !is_modified {
+                    file.children = old.children.take();
+                    file.recursive_size = old.recursive_size;
+                    file.checksum = old.checksum;
+                    file.wc = old.wc;
+                    file.preview = old.preview.take();
+
+                    if old.shebang_checked {
+                        file.is_executable |= old.is_executable;
+                        file.shebang_checked = true;
+                    }
+                }
+
+                file.parent = old.parent.or(file.parent);
+                file.depth = old.depth;
+
+                // `old` is overwritten in-place, so the references to it (e.g. `AppState::curr_instance`) are still valid
+                *old = file;
+            },
+            None => {
+                files.insert(uid, file);
+            },
+        }
+
+        uid
     }
 
     // it registers the instance to the cache, and only returns its uid
-    pub fn from_io_error(e: io::Error) -> Uid {
+    // it also pushes the error to the error log
+    pub fn from_io_error(e: io::Error, path: &str) -> Uid {
         let message = match e.kind() {
+            io::ErrorKind::NotFound => String::from("Not Found"),
             io::ErrorKind::PermissionDenied => String::from("Permission Denied"),
-            e => panic!("{e:?}"),
-        };
-        let message = format!("<<Error: {message}>>");
-        let uid = Uid::error();
-
-        let result = File {
-            uid,
-            name: message,
-            ..File::dummy()
+            io::ErrorKind::ReadOnlyFilesystem => String::from("Read-only Filesystem"),
+            io::ErrorKind::QuotaExceeded => String::from("Filesystem Quota Exceeded"),
+            kind => format!("IO Error: {kind:?}"),
         };
+        error_log_push(path, &message);
 
-        let files = unsafe { FILES.as_mut().unwrap() };
-        files.insert(uid, result);
-
-        uid
+        File::register_special_file(Uid::error(), format!("<<Error: {message}>>"))
     }
 
     // it registers the instance to the cache, and only returns its uid
-    pub fn from_error_msg(e: String) -> Uid {
+    // it also pushes the error to the error log
+    pub fn from_error_msg(e: String, path: &str) -> Uid {
+        error_log_push(path, if e.is_empty() { "Error" } else { &e });
+
         let message = if e.is_empty() {
             String::from("<<Error>>")
         } else {
             format!("<<Error: {e}>>")
         };
-        let uid = Uid::error();
-
-        let result = File {
-            uid,
-            name: message,
-            ..File::dummy()
-        };
-
-        let files = unsafe { FILES.as_mut().unwrap() };
-        files.insert(uid, result);
 
-        uid
+        File::register_special_file(Uid::error(), message)
     }
 
+    // it registers the instance to the cache, and only returns its uid
+    // a message is a special file that is shown as a row, like "Empty Directory"
     pub fn message_from_string(msg: String) -> Uid {
-        let uid = Uid::message();
-
-        let result = File {
-            name: msg,
-            uid,
-            ..File::dummy()
-        };
-
-        let files = unsafe { FILES.as_mut().unwrap() };
-        files.insert(uid, result);
-
-        uid
+        File::register_special_file(Uid::message(), msg)
     }
 
     // it registers the instance to the cache, and only returns its uid
@@ -263,12 +500,20 @@ impl File {
             return uid;
         }
 
-        let result = File {
+        File::register_special_file(
             uid,
-            name: format!(
+            format!(
                 "... (truncated {n} row{})",
                 if n < 2 { "" } else { "s" },
             ),
+        )
+    }
+
+    // errors and messages are created here
+    fn register_special_file(uid: Uid, name: String) -> Uid {
+        let result = File {
+            uid,
+            name,
             ..File::dummy()
         };
 
@@ -280,31 +525,178 @@ impl File {
 
     // It's safe (and recommended) to call this function multiple times.
     pub fn init_children(&mut self) {
+        self.init_children_with_progress(&mut |_| {});
+    }
+
+    // `on_progress` is called with the number of entries read so far, after each entry
+    pub fn init_children_with_progress(&mut self, on_progress: &mut dyn FnMut(usize)) {
+        if self.children.is_some() || !self.is_dir() {
+            return;
+        }
+
+        let self_path = try_get_path_by_uid(self.uid).unwrap();
+
+        self.init_children_from_iter(fs::read_dir(self_path), on_progress);
+    }
+
+    // it renames the file in the file system, and updates `name` and `PATHS` in-place
+    // `new_name` is a name, not a path
+    // the paths of the descendants are not updated, so the parent has to refresh its children
+    pub fn rename(&mut self, new_name: &str) -> Result<(), String> {
+        if new_name.is_empty() || new_name == "." || new_name == ".." || new_name.contains(std::path::is_separator) {
+            return Err(format!("invalid name: {new_name:?}"));
+        }
+
+        let old_path = match try_get_path_by_uid(self.uid) {
+            Some(path) => PathBuf::from(path),
+            None => {
+                return Err(format!("get_path_by_uid({}) has failed", self.uid));
+            },
+        };
+        let new_path = match old_path.parent() {
+            Some(parent) => parent.join(new_name),
+            None => {
+                return Err(String::from("cannot rename the root dir"));
+            },
+        };
+
+        if new_path.symlink_metadata().is_ok() {
+            return Err(format!("{new_name:?} already exists"));
+        }
+
+        fs::rename(&old_path, &new_path).map_err(|e| e.to_string())?;
+
+        self.name = new_name.to_string();
+        self.file_ext = new_path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_string());
+        self.is_hidden = new_name.starts_with('.') || new_path.symlink_metadata().map(|m| has_hidden_attribute(&m)).unwrap_or(false);
+
+        let paths = unsafe { PATHS.as_mut().unwrap() };
+        paths.insert(self.uid, new_path.to_string_lossy().to_string());
+
+        Ok(())
+    }
+
+    // it removes the file (or the dir and its contents) from the file system
+    // it doesn't unregister the file, see `unregister_file`
+    pub fn delete(&self) -> Result<(), String> {
+        let path = match try_get_path_by_uid(self.uid) {
+            Some(path) => path,
+            None => {
+                return Err(format!("get_path_by_uid({}) has failed", self.uid));
+            },
+        };
+
+        // a symlink to a dir is not a dir, so the link itself is removed
+        let result = if self.is_dir() {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        };
+
+        result.map_err(|e| e.to_string())
+    }
+
+    // it reads the children again, and invalidates `recursive_size` of itself and its ancestors
+    pub fn refresh_children(&mut self) {
+        if !self.is_dir() {
+            return;
+        }
+
+        self.children = None;
+        self.recursive_size = None;
+        self.init_children();
+
+        let mut parent = self.parent;
+
+        while let Some(uid) = parent {
+            match get_file_by_uid(uid) {
+                Some(f) => {
+                    f.recursive_size = None;
+                    parent = f.parent;
+                },
+                None => {
+                    break;
+                },
+            }
+        }
+    }
+
+    // it reads the dir in tokio's blocking pool, so that it doesn't block the async runtime
+    // it returns false if the children are already initialized
+    // like `init_children`, it must be called by the master thread, because it registers the children to `FILES`
+    #[cfg(feature = "tokio")]
+    pub async fn init_children_async(&mut self) -> bool {
+        if self.children.is_some() || !self.is_dir() {
+            return false;
+        }
+
+        let self_path = match try_get_path_by_uid(self.uid) {
+            Some(path) => path.to_string(),
+            None => {
+                return false;
+            },
+        };
+
+        // `tokio::fs::read_dir` gives `tokio::fs::DirEntry`, but `File::new_from_dir_entry` needs `fs::DirEntry`
+        // `tokio::fs::read_dir` runs `fs::read_dir` in the blocking pool anyway
+        let entries = match tokio::task::spawn_blocking(
+            move || fs::read_dir(self_path).map(|entries| entries.collect::<Vec<_>>())
+        ).await {
+            Ok(entries) => entries,
+            Err(e) => Err(io::Error::other(e)),
+        };
+
+        self.init_children_from_entries(entries);
+        true
+    }
+
+    // `entries` is the result of `fs::read_dir`, which might have been read by another thread
+    // it does nothing if the children are already initialized
+    pub fn init_children_from_entries(&mut self, entries: io::Result<Vec<io::Result<fs::DirEntry>>>) {
+        self.init_children_from_iter(entries.map(|entries| entries.into_iter()), &mut |_| {});
+    }
+
+    fn init_children_from_iter<I: Iterator<Item = io::Result<fs::DirEntry>>>(
+        &mut self,
+        entries: io::Result<I>,
+        on_progress: &mut dyn FnMut(usize),
+    ) {
         if self.children.is_some() || !self.is_dir() {
             return;
         }
 
-        let self_path = get_path_by_uid(self.uid).unwrap();
+        let self_path = try_get_path_by_uid(self.uid).map(|path| path.to_string()).unwrap_or_default();
 
-        match fs::read_dir(self_path) {
+        // it's cached per dir, because `statfs` might be slow on a network file system
+        self.is_network_mount = is_network_file_system(&self_path);
+
+        match entries {
             Ok(entries) => {
                 let mut result = vec![];
 
                 for entry in entries {
                     match entry {
                         Ok(e) => {
-                            result.push(File::new_from_dir_entry(e, Some(self.uid)));
+                            let child = File::new_from_dir_entry(e, Some(self.uid));
+
+                            if let Some(child) = get_file_by_uid(child) {
+                                child.is_network_mount = self.is_network_mount;
+                            }
+
+                            result.push(child);
                         },
                         Err(e) => {
-                            result.push(File::from_io_error(e));
+                            result.push(File::from_io_error(e, &self_path));
                         },
                     }
+
+                    on_progress(result.len());
                 }
 
                 self.children = Some(result);
             },
             Err(e) => {
-                self.children = Some(vec![File::from_io_error(e)]);
+                self.children = Some(vec![File::from_io_error(e, &self_path)]);
             },
         }
     }
@@ -317,8 +709,23 @@ impl File {
         !self.is_special_file() && matches!(self.file_type, FileType::File)
     }
 
+    // a file with 0 bytes, or a dir with no children (including hidden ones)
+    pub fn is_empty(&self) -> bool {
+        if self.is_file() {
+            self.size == 0
+        }
+
+        else if self.is_dir() {
+            self.get_children_num(true) == 0
+        }
+
+        else {
+            false
+        }
+    }
+
     pub fn is_hidden_file(&self) -> bool {
-        !self.is_special_file() && self.name.starts_with(".")
+        !self.is_special_file() && self.is_hidden
     }
 
     // not a file
@@ -333,8 +740,9 @@ impl File {
         }
 
         else {
-            let child_iter = self.children.as_ref().unwrap().iter().map(
-                |child| get_file_by_uid(*child).unwrap() as &File
+            // a child might have been unregistered (e.g. `;d`) by another instance of this dir
+            let child_iter = self.children.as_ref().unwrap().iter().filter_map(
+                |child| get_file_by_uid(*child).map(|child| child as &File)
             );
 
             if show_hidden_files {
@@ -349,6 +757,45 @@ impl File {
         }
     }
 
+    pub fn get_children_sorted(&self, show_hidden_files: bool, sort_by: ColumnKind, reverse: bool) -> Vec<&File> {
+        let mut children = self.get_children(show_hidden_files);
+        sort_files(&mut children, sort_by, reverse);
+
+        children
+    }
+
+    // all the files in the subtree (BFS order), except `self`
+    // `max_depth: Some(1)` is the same as `get_children`
+    // it stops reading dirs when `FILES` has too many entries
+    pub fn get_descendants(&self, show_hidden_files: bool, max_depth: Option<usize>) -> Vec<&File> {
+        let mut result = vec![];
+        let mut queue = VecDeque::from([(self as &File, 0)]);
+
+        while let Some((dir, depth)) = queue.pop_front() {
+            if max_depth.map(|max_depth| depth >= max_depth).unwrap_or(false) {
+                continue;
+            }
+
+            if unsafe { FILES.as_ref().unwrap().len() } > MAX_FILES_FOR_DESCENDANTS {
+                break;
+            }
+
+            for child in dir.get_children(show_hidden_files) {
+                if child.is_special_file() {
+                    continue;
+                }
+
+                if child.is_dir() {
+                    queue.push_back((child, depth + 1));
+                }
+
+                result.push(child);
+            }
+        }
+
+        result
+    }
+
     // it calls `init_children` if it has to
     pub fn get_children_num(&self, include_hidden_files: bool) -> usize {
         if self.is_dir() {
@@ -356,13 +803,14 @@ impl File {
                 Some(c) => if include_hidden_files {
                     c.len()
                 } else {
-                    c.iter().map(
-                        |uid| get_file_by_uid(*uid).unwrap()
+                    c.iter().filter_map(
+                        |uid| get_file_by_uid(*uid)
                     ).filter(
                         |c| !c.is_hidden_file()
                     ).count()
                 },
                 None => {
+                    debug_assert_uid_valid!(self.uid);
                     let very_unsafe_object = get_file_by_uid(self.uid).unwrap();
                     very_unsafe_object.init_children();
 
@@ -372,7 +820,10 @@ impl File {
 
                     else {
                         very_unsafe_object.children.as_ref().unwrap().iter().map(
-                            |uid| get_file_by_uid(*uid).unwrap() as &File
+                            |uid| {
+                                debug_assert_uid_valid!(*uid);
+                                get_file_by_uid(*uid).unwrap() as &File
+                            }
                         ).filter(
                             |c| !c.is_hidden_file()
                         ).count()
@@ -384,25 +835,85 @@ impl File {
         }
     }
 
+    // children of the parent dir, including `self`
+    // the root dir doesn't have any sibling
+    pub fn get_siblings(&self, show_hidden: bool) -> Vec<&File> {
+        if self.uid == Uid::ROOT || self.is_special_file() {
+            return vec![];
+        }
+
+        match get_file_by_uid(self.get_parent_uid()) {
+            Some(parent) => parent.get_children(show_hidden),
+            None => vec![],
+        }
+    }
+
+    // components of the path, borrowed from `PATHS`, like `["/", "home", "user"]`
+    // it's empty if the path is not registered
+    pub fn path_components<'a>(&self) -> Vec<&'a str> {
+        match try_get_path_by_uid(self.uid) {
+            // paths in `PATHS` are always valid utf-8
+            Some(path) => Path::new(path).components().map(|c| c.as_os_str().to_str().unwrap()).collect(),
+            None => vec![],
+        }
+    }
+
+    // number of `get_parent_uid` hops to `Uid::LAUNCH`
+    // it's 0 for `Uid::LAUNCH` and the files that are not inside `Uid::LAUNCH` (e.g. reached with `..`)
+    pub fn get_depth(&self) -> usize {
+        if let Some(depth) = self.depth {
+            return depth;
+        }
+
+        let mut depth = 0;
+        let mut curr_uid = self.uid;
+
+        while curr_uid != Uid::LAUNCH {
+            debug_assert_uid_valid!(curr_uid);
+            let curr_file = get_file_by_uid(curr_uid).unwrap();
+
+            if curr_uid == Uid::ROOT || curr_file.is_special_file() {
+                depth = 0;
+                break;
+            }
+
+            curr_uid = curr_file.get_parent_uid();
+            depth += 1;
+        }
+
+        // what an unsafe operation
+        debug_assert_uid_valid!(self.uid);
+        get_file_by_uid(self.uid).unwrap().depth = Some(depth);
+
+        depth
+    }
+
     pub fn get_parent_uid(&self) -> Uid {
         if !self.is_special_file() {
             match self.parent {
                 Some(uid) => uid,
                 None => {
-                    let path = get_path_by_uid(self.uid).unwrap();
+                    let path = try_get_path_by_uid(self.uid).unwrap();
                     let std_path = Path::new(path);
                     let parent_path = std_path.parent().unwrap().to_string_lossy().to_string();
 
-                    // TODO: better way to find the root dir
-                    let parent_uid = if parent_path == "/" {
-                        Uid::ROOT
-                    } else {
-                        Uid::normal_file()
+                    // if the parent is already registered (e.g. it's `Uid::LAUNCH`), it reuses the instance
+                    let parent_uid = match get_uid_by_path(&parent_path) {
+                        Some(uid) => uid,
+                        None => {
+                            // TODO: better way to find the root dir
+                            let parent_uid = if parent_path == "/" {
+                                Uid::ROOT
+                            } else {
+                                Uid::from_path(&parent_path)
+                            };
+
+                            File::new_from_dir_path(parent_path, Some(parent_uid), None)
+                        },
                     };
 
-                    let parent_uid = File::new_from_dir_path(parent_path, Some(parent_uid), None);
-
                     // what an unsafe operation
+                    debug_assert_uid_valid!(self.uid);
                     get_file_by_uid(self.uid).unwrap().parent = Some(parent_uid);
 
                     parent_uid
@@ -415,17 +926,53 @@ impl File {
         }
     }
 
+    // `get_parent_uid` panics if it's false
+    // `/` and the roots of windows drives (e.g. `C:\`) don't have parents
+    pub fn has_parent(&self) -> bool {
+        if self.is_special_file() {
+            false
+        }
+
+        else if self.parent.is_some() {
+            true
+        }
+
+        else {
+            match try_get_path_by_uid(self.uid) {
+                Some(path) => Path::new(path).parent().is_some(),
+                None => false,
+            }
+        }
+    }
+
+    // it doesn't block: if it's not calculated yet, it asks the workers and returns 0
+    // `print_dir` shows `...` while `WorkItem::ComputeRecursiveSize` is pending
     pub fn get_recursive_size(&self) -> u64 {
         match self.recursive_size {
             Some(s) => s,
-            None => {
-                let mut sum = 0;
+            // members of an archive are not in the real file system, but their children are always loaded
+            None if is_archive_member(self.uid) => self.get_recursive_size_blocking(),
+            // there's no worker pool before the first render
+            None => if send_work(WorkItem::ComputeRecursiveSize(self.uid)) {
+                0
+            } else {
+                self.get_recursive_size_blocking()
+            },
+        }
+    }
 
-                for child in self.get_children(true).iter() {
-                    sum += child.get_recursive_size();
-                }
+    pub fn get_recursive_size_blocking(&self) -> u64 {
+        match self.recursive_size {
+            Some(s) => s,
+            None => {
+                let sum = match (&self.children, try_get_path_by_uid(self.uid)) {
+                    // members of an archive are not in the real file system, but their children are always loaded
+                    (None, Some(path)) => get_total_dir_size_fast(path),
+                    _ => self.get_children(true).iter().map(|child| child.get_recursive_size_blocking()).sum(),
+                };
 
                 // what an unsafe operation
+                debug_assert_uid_valid!(self.uid);
                 get_file_by_uid(self.uid).unwrap().recursive_size = Some(sum);
 
                 sum
@@ -433,6 +980,115 @@ impl File {
         }
     }
 
+    // it only hashes files smaller than 1 MiB
+    // for the other files (and dirs), it returns None
+    pub fn get_checksum(&self) -> Option<[u8; 32]> {
+        match self.checksum {
+            Some(c) => Some(c),
+            None if self.is_file() && self.size < (1 << 20) => {
+                let path = try_get_path_by_uid(self.uid)?;
+                let content = fs::read(path).ok()?;
+                let checksum: [u8; 32] = Sha256::digest(&content).into();
+
+                // what an unsafe operation
+                debug_assert_uid_valid!(self.uid);
+                get_file_by_uid(self.uid).unwrap().checksum = Some(checksum);
+
+                Some(checksum)
+            },
+            None => None,
+        }
+    }
+
+    // a script with a shebang (`#!`) is executable with its interpreter, even without the executable bit
+    // it reads the first 2 bytes of the file only once, and caches the result in `is_executable`
+    pub fn get_is_executable(&self) -> bool {
+        if self.is_executable || self.shebang_checked || !self.is_file() {
+            return self.is_executable;
+        }
+
+        match &self.file_ext {
+            Some(ext) if SCRIPT_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()) => {},
+            _ => {
+                return false;
+            },
+        }
+
+        let mut shebang = [0; 2];
+
+        // members of an archive are not in the real file system
+        let has_shebang = match read_archive_member(self.uid) {
+            Some(bytes) => bytes.map(|bytes| bytes.starts_with(b"#!")).unwrap_or(false),
+            None => match try_get_path_by_uid(self.uid).map(fs::File::open) {
+                Some(Ok(f)) => {
+                    #[cfg(unix)]
+                    let bytes_read = f.read_at(&mut shebang, 0);
+
+                    #[cfg(not(unix))]
+                    let bytes_read = f.seek_read(&mut shebang, 0);
+
+                    matches!(bytes_read, Ok(2)) && shebang == *b"#!"
+                },
+                _ => false,
+            },
+        };
+
+        // what an unsafe operation
+        debug_assert_uid_valid!(self.uid);
+        let file = get_file_by_uid(self.uid).unwrap();
+        file.is_executable = has_shebang;
+        file.shebang_checked = true;
+
+        has_shebang
+    }
+
+    // it reads the first 256 bytes of the file
+    // for dirs (and unreadable files), it returns None
+    pub fn get_preview(&self) -> Option<String> {
+        match &self.preview {
+            Some(p) => Some(p.to_string()),
+            None if self.is_file() => {
+                let mut content = vec![0; PREVIEW_BYTES];
+
+                // members of an archive are not in the real file system
+                match read_archive_member(self.uid) {
+                    Some(bytes) => {
+                        let bytes = bytes.ok()?;
+                        content = bytes[..bytes.len().min(PREVIEW_BYTES)].to_vec();
+                    },
+                    None => {
+                        let path = try_get_path_by_uid(self.uid)?;
+                        let f = fs::File::open(path).ok()?;
+
+                        #[cfg(unix)]
+                        let bytes_read = f.read_at(&mut content, 0).ok()?;
+
+                        #[cfg(not(unix))]
+                        let bytes_read = f.seek_read(&mut content, 0).ok()?;
+
+                        content.truncate(bytes_read);
+                    },
+                }
+
+                let preview = match try_extract_utf8_text(&content) {
+                    Some(s) => s.lines().next().unwrap_or("").chars().filter(
+                        |c| !c.is_control()
+                    ).take(80).collect::<String>(),
+                    None => content.iter().take(4).map(
+                        |b| format!("{b:02x}")
+                    ).collect::<Vec<_>>().join(" "),
+                };
+
+                // what an unsafe operation
+                debug_assert_uid_valid!(self.uid);
+                get_file_by_uid(self.uid).unwrap().preview = Some(preview.clone());
+
+                Some(preview)
+            },
+            None => None,
+        }
+    }
+
     // make sure that nobody reads these values
     pub fn dummy() -> Self {
         File {
@@ -442,10 +1098,22 @@ impl File {
             last_modified: SystemTime::now(),
             size: 0,
             recursive_size: None,
+            depth: None,
+            checksum: None,
+            wc: None,
+            preview: None,
             file_type: FileType::File,
             file_ext: None,
             children: None,
             is_executable: false,
+            shebang_checked: false,
+            inode: None,
+            nlink: 1,
+            created_at: None,
+            last_opened: None,
+            mode: None,
+            is_hidden: false,
+            is_network_mount: false,
         }
     }
 
@@ -453,7 +1121,7 @@ impl File {
         let parent_info = match self.parent {
             Some(p) => format!(
                 "Some({:?})",
-                get_path_by_uid(p),
+                try_get_path_by_uid(p),
             ),
             None => String::from("None"),
         };
@@ -473,66 +1141,72 @@ impl File {
     }
 }
 
-pub fn iterate_paths(start: Uid, paths: &[String]) -> Option<Uid> {  // TODO: Result<Uid, Error>
-    if paths.is_empty() {
-        Some(start)
-    }
-
-    else if paths[0] == "." {
-        iterate_paths(start, &paths[1..])
-    }
-
-    else if paths[0] == ".." {
-        match get_file_by_uid(start) {
-            Some(f) if start != Uid::ROOT => iterate_paths(f.get_parent_uid(), &paths[1..]),
-            _ => None,
-        }
-    }
-
-    else {
-        match get_file_by_uid(start) {
-            Some(f) if f.is_dir() => {
-                for child in f.get_children(true) {
-                    if child.name == paths[0] {
-                        return iterate_paths(child.uid, &paths[1..]);
-                    }
-                }
-
-                None
-            },
-            _ => None,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{lock_globals, make_temp_dir};
+
+    #[test]
+    fn from_io_error_does_not_panic() {
+        let _globals = lock_globals();
+
+        for (kind, expected) in [
+            (io::ErrorKind::NotFound, "<<Error: Not Found>>"),
+            (io::ErrorKind::PermissionDenied, "<<Error: Permission Denied>>"),
+            (io::ErrorKind::ReadOnlyFilesystem, "<<Error: Read-only Filesystem>>"),
+            (io::ErrorKind::QuotaExceeded, "<<Error: Filesystem Quota Exceeded>>"),
+            (io::ErrorKind::ConnectionRefused, "<<Error: IO Error: ConnectionRefused>>"),
+            (io::ErrorKind::Other, "<<Error: IO Error: Other>>"),
+        ] {
+            let uid = File::from_io_error(io::Error::from(kind), "/some/path");
+
+            assert!(uid.is_special());
+            assert_eq!(get_file_by_uid(uid).unwrap().name, expected);
         }
     }
-}
-
-pub fn search_by_prefix(curr_file: Uid, paths: &[String]) -> Option<Uid> {  // TODO: Result<Uid, Error>
-    if paths.len() != 1 {
-        None
-    }
 
-    else {
-        let file_name = paths[0].to_ascii_lowercase();
+    #[test]
+    fn reading_a_dir_again_keeps_the_children() {
+        let _globals = lock_globals();
+        let (_, dir) = make_temp_dir("reread", &["sub/", "sub/f"]);
 
-        if let Some(f) = get_file_by_uid(curr_file) {
-            let mut results = vec![];
+        let sub = get_file_by_uid(dir).unwrap().get_children(true)[0].uid;
+        assert_eq!(get_file_by_uid(sub).unwrap().get_children(true).len(), 1);
+        get_file_by_uid(sub).unwrap().recursive_size = Some(42);
 
-            for child in f.get_children(true) {
-                if child.name.to_ascii_lowercase().starts_with(&file_name) {
-                    results.push(child.uid);
-                }
-            }
+        get_file_by_uid(dir).unwrap().refresh_children();
 
-            if results.len() == 1 {
-                Some(results[0])
-            }
+        let children = get_file_by_uid(dir).unwrap().get_children(true);
+        assert_eq!(children.len(), 1);
+        assert!(children[0].uid == sub);
+        assert!(children[0].children.is_some());
+        assert_eq!(children[0].recursive_size, Some(42));
+        assert!(children[0].parent == Some(dir));
+    }
 
-            else {
-                None
-            }
+    // `new_from_path_buf` uses `file_name`, and `get_prefix_name` if it's `None`
+    #[cfg(windows)]
+    #[test]
+    fn names_of_windows_paths() {
+        assert_eq!(PathBuf::from(r"\\?\C:\Users\test").file_name().unwrap(), "test");
+        assert_eq!(PathBuf::from(r"\\server\share\test").file_name().unwrap(), "test");
+
+        for (path, name) in [
+            (r"\\server\share", r"\\server\share"),
+            (r"\\server\share\", r"\\server\share"),
+            (r"\\?\UNC\server\share", r"\\server\share"),
+            (r"\\?\C:\", "C:"),
+            (r"C:\", "C:"),
+        ] {
+            assert!(PathBuf::from(path).file_name().is_none());
+            assert_eq!(get_prefix_name(&PathBuf::from(path)).as_deref(), Some(name));
         }
+    }
 
-        else {
-            None
-        }
+    #[cfg(unix)]
+    #[test]
+    fn unix_paths_have_no_prefix_name() {
+        assert!(get_prefix_name(&PathBuf::from("/")).is_none());
+        assert!(get_prefix_name(&PathBuf::from("/home/user")).is_none());
     }
 }
@@ -0,0 +1,64 @@
+use crate::utils::get_data_path;
+use std::fs;
+
+const MAX_HISTORY_LEN: usize = 200;
+
+/// Commands that the user has executed, from the oldest to the newest.
+/// It's persisted to `~/.local/share/file_query/history`.
+pub struct History {
+    entries: Vec<String>,
+}
+
+impl History {
+    /// It returns an empty history if there's no history file.
+    pub fn load() -> Self {
+        let entries = match get_data_path("history").map(fs::read_to_string) {
+            Some(Ok(s)) => s.lines().filter(
+                |line| !line.is_empty()
+            ).map(
+                |line| line.to_string()
+            ).collect::<Vec<_>>(),
+            _ => vec![],
+        };
+
+        let mut result = History { entries };
+        result.truncate();
+        result
+    }
+
+    /// It ignores empty commands, and collapses consecutive duplicate commands.
+    pub fn push(&mut self, command: &str) {
+        if command.is_empty() || command.contains('\n') || self.entries.last().map(|s| s.as_str()) == Some(command) {
+            return;
+        }
+
+        self.entries.push(command.to_string());
+        self.truncate();
+        self.save();
+    }
+
+    pub(crate) fn get(&self, index: usize) -> Option<&String> {
+        self.entries.get(index)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    // it doesn't bother the user if it fails to save the history
+    fn save(&self) {
+        if let Some(path) = get_data_path("history") {
+            if let Some(parent) = path.parent() {
+                let _ = fs::create_dir_all(parent);
+            }
+
+            let _ = fs::write(path, self.entries.join("\n"));
+        }
+    }
+
+    fn truncate(&mut self) {
+        if self.entries.len() > MAX_HISTORY_LEN {
+            self.entries = self.entries[(self.entries.len() - MAX_HISTORY_LEN)..].to_vec();
+        }
+    }
+}
@@ -0,0 +1,474 @@
+use crate::colors;
+use crate::history::History;
+use crate::uid::Uid;
+use crate::utils::{get_file_by_uid, iterate_paths, split_path_input};
+use colored::Colorize;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{self, ClearType};
+use std::io::{self, Write};
+use std::time::Duration;
+
+// `read_command` calls `on_idle` this often while there's no input
+const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+struct InputState {
+    buffer: String,
+
+    // candidates of tab-completion
+    // it's cleared when any non-Tab key is pressed
+    completions: Vec<String>,
+
+    // `None` until the user presses Tab twice
+    completion_index: Option<usize>,
+
+    // the input when the completions were made
+    // the rest of the input is rendered in a different color
+    completion_base: String,
+
+    // index of `History` that the user is browsing with ↑ and ↓
+    // it's reset when any key other than ↑ and ↓ is pressed
+    history_index: Option<usize>,
+
+    // the input before the user pressed ↑
+    // it only shows the history entries that start with this prefix
+    history_prefix: String,
+
+    // it's rendered before the input, and is not a part of the result
+    prompt: String,
+
+    // Escape cancels the input, instead of just clearing the completions
+    cancellable: bool,
+}
+
+impl InputState {
+    fn new() -> Self {
+        InputState {
+            buffer: String::new(),
+            completions: vec![],
+            completion_index: None,
+            completion_base: String::new(),
+            history_index: None,
+            history_prefix: String::new(),
+            prompt: String::new(),
+            cancellable: false,
+        }
+    }
+
+    fn clear_completions(&mut self) {
+        self.completions = vec![];
+        self.completion_index = None;
+        self.completion_base = String::new();
+    }
+
+    // 1. if there's only one candidate, it completes the input
+    // 2. if there are multiple candidates, it completes the longest common prefix of them
+    // 3. if the user presses Tab again, it cycles through the candidates
+    fn complete(&mut self, get_completions: &dyn Fn(&str) -> Vec<String>) {
+        if !self.completions.is_empty() {
+            let index = match self.completion_index {
+                Some(index) => (index + 1) % self.completions.len(),
+                None => 0,
+            };
+
+            self.completion_index = Some(index);
+            self.buffer = self.completions[index].clone();
+            return;
+        }
+
+        let completions = get_completions(&self.buffer);
+
+        if completions.len() == 1 {
+            self.buffer = completions[0].clone();
+        }
+
+        else if completions.len() > 1 {
+            let prefix = longest_common_prefix(&completions);
+
+            if prefix.len() > self.buffer.len() {
+                self.buffer = prefix;
+            }
+
+            self.completion_base = self.buffer.clone();
+            self.completions = completions;
+        }
+    }
+
+    fn history_prev(&mut self, history: &History) {
+        let end = match self.history_index {
+            Some(index) => index,
+            None => {
+                self.history_prefix = self.buffer.clone();
+                history.len()
+            },
+        };
+
+        for index in (0..end).rev() {
+            let entry = history.get(index).unwrap();
+
+            if entry.starts_with(&self.history_prefix) {
+                self.history_index = Some(index);
+                self.buffer = entry.clone();
+                return;
+            }
+        }
+    }
+
+    fn history_next(&mut self, history: &History) {
+        let start = match self.history_index {
+            Some(index) => index + 1,
+            None => {
+                return;
+            },
+        };
+
+        for index in start..history.len() {
+            let entry = history.get(index).unwrap();
+
+            if entry.starts_with(&self.history_prefix) {
+                self.history_index = Some(index);
+                self.buffer = entry.clone();
+                return;
+            }
+        }
+
+        // the user has reached the end of the history
+        self.history_index = None;
+        self.buffer = self.history_prefix.clone();
+    }
+
+    fn render(&self) {
+        let mut stdout = io::stdout();
+
+        print!("\r");
+        let _ = crossterm::execute!(stdout, terminal::Clear(ClearType::CurrentLine));
+        print!("{}", self.prompt);
+
+        match self.completion_index {
+            Some(_) if self.buffer.starts_with(&self.completion_base) => {
+                print!(
+                    "{}{}",
+                    self.completion_base,
+                    self.buffer.get(self.completion_base.len()..).unwrap().color(colors::YELLOW),
+                );
+            },
+            _ => {
+                print!("{}", self.buffer);
+            },
+        }
+
+        let _ = stdout.flush();
+    }
+}
+
+/// It reads a line from the terminal, char by char. The result does not have the trailing newline.
+/// It falls back to `io::stdin().read_line` if the terminal doesn't support the raw mode.
+/// When the user presses Tab, it calls `get_completions` with the current input.
+/// ↑ and ↓ cycle through `history`, and the command is pushed to `history`.
+/// While there's no input, it calls `on_idle` periodically. If `on_idle` redraws the screen,
+/// it must return true, and the prompt is drawn again.
+pub fn read_command(
+    history: &mut History,
+    get_completions: &dyn Fn(&str) -> Vec<String>,
+    on_idle: Option<&mut dyn FnMut() -> bool>,
+) -> String {
+    if terminal::enable_raw_mode().is_err() {
+        let mut buffer = String::new();
+        io::stdin().read_line(&mut buffer).unwrap();
+
+        let buffer = buffer.trim_end_matches(['\r', '\n']).to_string();
+        history.push(&buffer);
+
+        return buffer;
+    }
+
+    let mut state = InputState::new();
+    read_in_raw_mode(&mut state, Some(history), get_completions, None, on_idle);
+
+    history.push(&state.buffer);
+    state.buffer
+}
+
+/// It shows `prompt` and reads a line whose initial value is `default`, so that the user can edit it.
+/// It doesn't touch the history, and Tab does nothing.
+pub fn read_line_with_default(prompt: &str, default: &str) -> String {
+    if terminal::enable_raw_mode().is_err() {
+        print!("{prompt}");
+        let _ = io::stdout().flush();
+
+        let mut buffer = String::new();
+        io::stdin().read_line(&mut buffer).unwrap();
+
+        let buffer = buffer.trim_end_matches(['\r', '\n']);
+
+        // there's no way to edit `default` without the raw mode
+        return if buffer.is_empty() { default.to_string() } else { buffer.to_string() };
+    }
+
+    let mut state = InputState::new();
+    state.prompt = prompt.to_string();
+    state.buffer = default.to_string();
+    state.render();
+
+    read_in_raw_mode(&mut state, None, &|_| vec![], None, None);
+    state.buffer
+}
+
+/// It shows `prompt` and reads a line, calling `on_change` with the input whenever it changes.
+/// `on_change` may redraw the screen, and the prompt is drawn again after that.
+/// It returns `None` if the user presses Escape.
+pub fn read_line_live(prompt: &str, on_change: &mut dyn FnMut(&str)) -> Option<String> {
+    if terminal::enable_raw_mode().is_err() {
+        print!("{prompt}");
+        let _ = io::stdout().flush();
+
+        let mut buffer = String::new();
+        io::stdin().read_line(&mut buffer).unwrap();
+
+        let buffer = buffer.trim_end_matches(['\r', '\n']).to_string();
+        on_change(&buffer);
+
+        return Some(buffer);
+    }
+
+    let mut state = InputState::new();
+    state.prompt = prompt.to_string();
+    state.cancellable = true;
+    state.render();
+
+    if read_in_raw_mode(&mut state, None, &|_| vec![], Some(on_change), None) {
+        Some(state.buffer)
+    }
+
+    else {
+        None
+    }
+}
+
+/// It returns when the user presses any key (or Enter, if the terminal doesn't support the raw mode).
+pub fn wait_for_key() {
+    if terminal::enable_raw_mode().is_err() {
+        let _ = io::stdin().read_line(&mut String::new());
+        return;
+    }
+
+    loop {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind != KeyEventKind::Release => {
+                break;
+            },
+            Ok(_) => {
+                continue;
+            },
+            Err(_) => {
+                break;
+            },
+        }
+    }
+
+    terminal::disable_raw_mode().unwrap();
+}
+
+/// It returns true if the user has pressed a key, without blocking. The key is consumed.
+/// The caller has to enable the raw mode, otherwise the keys are not seen until Enter.
+pub fn is_key_pressed() -> bool {
+    while event::poll(Duration::ZERO).unwrap_or(false) {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind != KeyEventKind::Release => {
+                return true;
+            },
+            Ok(_) => {
+                continue;
+            },
+            Err(_) => {
+                return false;
+            },
+        }
+    }
+
+    false
+}
+
+/// It reads a key press. The arrow keys are `h`, `j`, `k` and `l`, and Escape is `None`.
+/// If the terminal doesn't support the raw mode, it reads a line and returns its first character.
+pub fn read_key() -> Option<char> {
+    if terminal::enable_raw_mode().is_err() {
+        let mut buffer = String::new();
+        let _ = io::stdin().read_line(&mut buffer);
+
+        return buffer.chars().next().filter(|c| *c != '\n');
+    }
+
+    let result = loop {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind != KeyEventKind::Release => match key.code {
+                KeyCode::Char(c) => break Some(c),
+                KeyCode::Left => break Some('h'),
+                KeyCode::Down => break Some('j'),
+                KeyCode::Up => break Some('k'),
+                KeyCode::Right => break Some('l'),
+                KeyCode::Enter => break Some('\n'),
+                KeyCode::Esc => break None,
+                _ => {
+                    continue;
+                },
+            },
+            Ok(_) => {
+                continue;
+            },
+            Err(_) => {
+                break None;
+            },
+        }
+    };
+
+    terminal::disable_raw_mode().unwrap();
+    result
+}
+
+// the raw mode must be enabled before calling this, and it disables the raw mode
+// it returns false if the input is cancelled
+fn read_in_raw_mode(
+    state: &mut InputState,
+    history: Option<&History>,
+    get_completions: &dyn Fn(&str) -> Vec<String>,
+    mut on_change: Option<&mut dyn FnMut(&str)>,
+    mut on_idle: Option<&mut dyn FnMut() -> bool>,
+) -> bool {
+    let mut is_cancelled = false;
+
+    loop {
+        if let Some(on_idle) = &mut on_idle {
+            if !event::poll(IDLE_POLL_INTERVAL).unwrap_or(true) {
+                // the screen is drawn in the cooked mode, like `on_change`
+                terminal::disable_raw_mode().unwrap();
+                let is_redrawn = on_idle();
+                terminal::enable_raw_mode().unwrap();
+
+                if is_redrawn {
+                    state.render();
+                }
+
+                continue;
+            }
+        }
+
+        let key = match event::read() {
+            Ok(Event::Key(key)) if key.kind != KeyEventKind::Release => key,
+            Ok(_) => {
+                continue;
+            },
+            Err(_) => {
+                break;
+            },
+        };
+
+        if !matches!(key.code, KeyCode::Up | KeyCode::Down) {
+            state.history_index = None;
+        }
+
+        match key.code {
+            // the terminal doesn't send SIGINT in the raw mode
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                terminal::disable_raw_mode().unwrap();
+                println!();
+                std::process::exit(130);
+            },
+            KeyCode::Enter => {
+                break;
+            },
+            KeyCode::Esc if state.cancellable => {
+                is_cancelled = true;
+                break;
+            },
+            KeyCode::Up => if let Some(history) = history {
+                state.clear_completions();
+                state.history_prev(history);
+            },
+            KeyCode::Down => if let Some(history) = history {
+                state.clear_completions();
+                state.history_next(history);
+            },
+            KeyCode::Tab => {
+                state.complete(get_completions);
+            },
+            KeyCode::Backspace => {
+                state.clear_completions();
+                state.buffer.pop();
+            },
+            KeyCode::Char(c) => {
+                state.clear_completions();
+                state.buffer.push(c);
+            },
+            _ => {
+                state.clear_completions();
+            },
+        }
+
+        if let Some(on_change) = &mut on_change {
+            if matches!(key.code, KeyCode::Backspace | KeyCode::Char(_)) {
+                // the screen is drawn in the cooked mode, where `\n` goes to the start of the next line
+                terminal::disable_raw_mode().unwrap();
+                on_change(&state.buffer);
+                terminal::enable_raw_mode().unwrap();
+            }
+        }
+
+        state.render();
+    }
+
+    terminal::disable_raw_mode().unwrap();
+    println!();
+
+    !is_cancelled
+}
+
+/// It completes the last component of `input`, which is a path relative to `base`.
+/// Directories end with `/`. Hidden files are included only if the last component starts with `.`.
+pub fn complete_path(base: Uid, input: &str) -> Vec<String> {
+    let (dir, prefix) = match input.rsplit_once(['/', '\\']) {
+        Some((dir, prefix)) => (Some(dir), prefix),
+        None => (None, input),
+    };
+
+    let dir_uid = match dir {
+        Some(dir) => match iterate_paths(base, &split_path_input(dir)) {
+            Some(uid) => uid,
+            None => {
+                return vec![];
+            },
+        },
+        None => base,
+    };
+
+    let dir_file = match get_file_by_uid(dir_uid) {
+        Some(f) if f.is_dir() => f,
+        _ => {
+            return vec![];
+        },
+    };
+
+    let mut result = dir_file.get_children(prefix.starts_with('.')).into_iter().filter(
+        |child| !child.is_special_file() && child.name.starts_with(prefix)
+    ).map(
+        |child| format!(
+            "{}{}{}",
+            match dir { Some(dir) => format!("{dir}/"), None => String::new() },
+            child.name,
+            if child.is_dir() { "/" } else { "" },
+        )
+    ).collect::<Vec<_>>();
+
+    result.sort();
+    result
+}
+
+fn longest_common_prefix(strings: &[String]) -> String {
+    let mut result = strings[0].chars().collect::<Vec<_>>();
+
+    for s in strings[1..].iter() {
+        let common_len = result.iter().zip(s.chars()).take_while(|(a, b)| **a == *b).count();
+        result.truncate(common_len);
+    }
+
+    result.into_iter().collect()
+}
@@ -2,20 +2,56 @@
 
 use std::collections::HashMap;
 
+// it panics with a helpful message if `uid` is not in `FILES`
+// it does nothing in release builds
+#[macro_export]
+macro_rules! debug_assert_uid_valid {
+    ($uid:expr) => {
+        if cfg!(debug_assertions) && !$crate::is_uid_registered($uid) {
+            panic!("UID {} is not registered in FILES", $uid);
+        }
+    };
+}
+
+mod archive;
 mod colors;
+mod error;
 mod file;
+mod history;
+mod input;
+mod preferences;
 mod print;
+mod search;
+mod session;
+mod tags;
+
+#[cfg(test)]
+mod testing;
+
 mod uid;
 mod utils;
+mod worker;
 
-pub use file::{iterate_paths, search_by_prefix, File, FileType};
+pub use archive::{enter_archive, is_archive_member, NavigationMode};
+pub use error::FileQueryError;
+pub use file::{File, FileType};
+pub use history::History;
+pub use input::{complete_path, is_key_pressed, read_command, read_key, read_line_live, read_line_with_default, wait_for_key};
+pub use preferences::{load_preferences, reload_preferences, save_preference};
 pub use print::{
     flip_buffer,
+    get_visible_children,
     print_dir,
+    print_duplicates,
     print_error_message,
     print_file,
     print_link,
+    print_stat,
+    ColumnKind,
+    DuplicateGroup,
+    FileEncoding,
     FileReadMode,
+    PermissionsFormat,
     PrintDirConfig,
     PrintFileConfig,
     PrintLinkConfig,
@@ -23,9 +59,48 @@ pub use print::{
     PrintFileResult,
     PrintLinkResult,
     ViewerKind,
+    SYNTAX_THEMES,
 };
+pub use search::{
+    find_byte_sequence,
+    find_matched_lines,
+    get_match_count,
+    grep_dir,
+    parse_byte_pattern,
+    set_grep_results,
+};
+pub use session::save_session;
+pub use tags::{get_tag, set_tag, TagColor};
 pub use uid::Uid;
-pub use utils::{get_file_by_uid, get_path_by_uid};
+pub use utils::{
+    copy_to_clipboard,
+    get_breadcrumb,
+    get_file_by_uid,
+    get_path_by_uid,
+    get_total_dir_size_fast,
+    get_uid_by_path,
+    get_xattr,
+    intern_xattr_key,
+    is_uid_registered,
+    iterate_paths,
+    remove_xattr,
+    search_by_prefix,
+    set_xattr,
+    shell_quote,
+    sort_files,
+    split_path_input,
+    try_get_path_by_uid,
+    unregister_file,
+};
+pub use worker::{
+    collect_work_results,
+    is_work_pending,
+    send_work,
+    WorkItem,
+    WorkerPool,
+    WORKER_NUM,
+    WORKER_POOL,
+};
 
 pub static mut IS_MASTER_WORKING: bool = false;
 pub static mut FILES: *mut HashMap<Uid, File> = std::ptr::null_mut();
@@ -1,8 +1,22 @@
+use commands::{complete_command, dir_commands, file_commands, goto_nth_file, run_command, AppState};
 use hfile::*;
 use regex::Regex;
 use std::{fs, thread, time};
 use std::collections::HashMap;
-use std::io::{self, BufRead, BufReader};
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+
+mod commands;
+
+// `;m<N>` doesn't accept anything wider than this
+const MAX_COLUMN_MARGIN: usize = 8;
+
+// `;n<N>` (and `<N>`) doesn't accept anything larger than this
+const MAX_GOTO_INDEX: usize = 9999;
+
+// `;L<N>` doesn't accept anything deeper than this
+// TODO: `add_nested_contents` can go deeper, but the rows get too crowded
+const MAX_NESTED_LEVEL: usize = 2;
 
 fn main() {
     unsafe { IS_MASTER_WORKING = true; }
@@ -16,6 +30,37 @@ fn main() {
     let mut print_file_config = PrintFileConfig::default();
     let mut print_link_config = PrintLinkConfig::default();
 
+    load_preferences(&mut print_dir_config);
+
+    // https://no-color.org, `--color=always` still overrides it
+    if is_no_color_set() {
+        colored::control::set_override(false);
+    }
+
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--recursive" | "--flat" => {
+                print_dir_config.recursive = true;
+            },
+            // even when the output is piped
+            "--color=always" => {
+                colored::control::set_override(true);
+            },
+            // `colored` checks whether stdout is a terminal
+            "--color=auto" => {
+                colored::control::unset_override();
+
+                if is_no_color_set() {
+                    colored::control::set_override(false);
+                }
+            },
+            "--color=never" => {
+                colored::control::set_override(false);
+            },
+            _ => {},
+        }
+    }
+
     // TODO: it's inefficient to handle 3 (almost) identical configs
     print_dir_config.adjust_output_dimension();
     print_file_config.adjust_output_dimension();
@@ -43,13 +88,17 @@ fn main() {
 
     match std::env::current_dir() {
         Ok(dir) => {
-            File::new_from_path_buf(dir, Some(Uid::BASE), None);
+            File::new_from_path_buf(dir, Some(Uid::LAUNCH), None);
+
+            if let Ok(home) = std::env::var("HOME") {
+                File::new_from_path_buf(PathBuf::from(home), Some(Uid::HOME), None);
+            }
         },
         Err(e) => {
             print_error_message(
                 None,
                 None,
-                format!("{e:?}"),
+                e.into(),
                 print_dir_config.min_width,
                 print_dir_config.max_width,
             );
@@ -57,108 +106,141 @@ fn main() {
         },
     }
 
-    let mut curr_uid = Uid::BASE;
-    let mut curr_instance = get_file_by_uid(curr_uid).unwrap();
+    debug_assert_uid_valid!(Uid::LAUNCH);
+    let mut state = AppState {
+        curr_uid: Uid::LAUNCH,
+        curr_instance: get_file_by_uid(Uid::LAUNCH).unwrap(),
+        navigation_mode: NavigationMode::FileSystem,
+        print_dir_config,
+        print_file_config,
+        print_link_config,
+        history: History::load(),
+    };
     let mut curr_mode = FileType::Dir;
+    let dir_commands = dir_commands();
+    let file_commands = file_commands();
 
     let mut previous_print_dir_result = PrintDirResult::dummy();
     let mut previous_print_file_result = PrintFileResult::dummy();
     let mut previous_print_link_result = PrintLinkResult::dummy();
 
-    // Uid::BASE must point to a directory
-    print_dir(curr_uid, &print_dir_config);
+    // the first render already sends works, e.g. `ColumnKind::TotalSize`
+    let mut worker_pool = Box::new(WorkerPool::new(WORKER_NUM));
+
+    unsafe {
+        WORKER_POOL = worker_pool.as_mut() as *mut WorkerPool;
+    }
+
+    // Uid::LAUNCH must point to a directory
+    print_dir(state.curr_uid, &state.print_dir_config);
     flip_buffer(is_interactive_mode);
+    save_session();
 
     unsafe { IS_MASTER_WORKING = false; }
 
-    // TODO: spawn_workers here
-
     // TODO: use rustyline or reedline
     if is_interactive_mode {
         loop {
             match curr_mode {
                 FileType::Dir => {
                     // TODO: better parsing... or Rusty Line!
-                    let mut buffer = String::new();
-                    io::stdin().read_line(&mut buffer).unwrap();
-                    print_dir_config.reset_alert();
-
-                    buffer = buffer.strip_suffix("\n").unwrap().to_string();
+                    let buffer = read_command(
+                        &mut state.history,
+                        &|input| complete_command(state.curr_uid, input),
+                        Some(&mut || redraw_dir_on_work_results(state.curr_uid, &state.print_dir_config)),
+                    );
+                    state.print_dir_config.reset_alert();
 
-                    let mut paths = buffer.split('/').map(|p| p.to_string()).collect::<Vec<_>>();
+                    let mut paths = split_path_input(&buffer);
 
                     // `../../Music/` -> `../../Music`
                     // TODO: what if `Music` is a file, not a directory?
                     // TODO: it doesn't work if the path starts with `/`
-                    if paths.last() == Some(&String::new()) {
+                    if paths.last() == Some(&"") {
                         paths.pop().unwrap();
                     }
 
                     let chars = buffer.chars().collect::<Vec<char>>();
 
                     match chars.get(0) {
-                        Some('~') => {
-                            curr_uid = Uid::BASE;
-                            curr_instance = get_file_by_uid(curr_uid).unwrap();
+                        // `~~` is the launch dir
+                        Some('~') if chars.get(1) == Some(&'~') => {
+                            state.set_curr_uid(Uid::LAUNCH);
+                            state.print_dir_config.offset = 0;
                         },
-                        // FIXME: an error with file viewer -> try `;100` when there's less than 100 files
-                        // TODO: code is duplicated
-                        Some(';') => match chars.get(1) {  // special commands
-                            Some('j') => match chars.get(2) {
-                                Some('j') => match chars.get(3) {
-                                    Some('j') => {
-                                        print_dir_config.offset += 100;
-                                    },
-                                    _ => {
-                                        print_dir_config.offset += 10;
-                                    },
-                                },
-                                Some(c) if '0' <= *c && *c <= '9' => {
-                                    let n = parse_int_from(&chars[2..]);
-                                    print_dir_config.offset += n as usize;
-                                },
-                                _ => {
-                                    print_dir_config.offset += 1;
-                                },
-                            },
-                            Some('k') => match chars.get(2) {
-                                Some('k') => match chars.get(3) {
-                                    Some('k') => {
-                                        print_dir_config.offset = print_dir_config.offset.max(100) - 100;
-                                    },
-                                    _ => {
-                                        print_dir_config.offset = print_dir_config.offset.max(10) - 10;
-                                    },
-                                },
-                                Some(c) if '0' <= *c && *c <= '9' => {
-                                    let n = parse_int_from(&chars[2..]) as usize;
-                                    print_dir_config.offset = print_dir_config.offset.max(n) - n;
-                                },
-                                _ => {
-                                    print_dir_config.offset = print_dir_config.offset.max(1) - 1;
-                                },
+                        Some('~') => match get_file_by_uid(Uid::HOME) {
+                            Some(home) if home.is_dir() => {
+                                state.set_curr_uid(Uid::HOME);
+                                state.print_dir_config.offset = 0;
                             },
-                            Some(c) if '0' <= *c && *c <= '9' => {
-                                let n = parse_int_from(&chars[1..]);
-                                print_dir_config.offset = n as usize;
+                            _ => {
+                                state.print_dir_config.alert = String::from("$HOME is not available, use `~~` for the launch dir");
                             },
-                            // TODO: GOTO nth file, not just moving the offset
-                            _ => {},
                         },
-                        _ => if let Some(uid) = iterate_paths(curr_uid, &paths) {
-                            curr_uid = uid;
-                            curr_instance = get_file_by_uid(curr_uid).unwrap();
-                            print_dir_config.offset = 0;
+                        Some('c') if chars.len() == 1 => {
+                            state.print_dir_config.alert = copy_path_to_clipboard(state.curr_uid, false);
+                        },
+                        // copies the path, quoted for shells
+                        Some('C') if chars.len() == 1 => {
+                            state.print_dir_config.alert = copy_path_to_clipboard(state.curr_uid, true);
+                        },
+                        // refreshes the current dir
+                        Some('.') if chars.len() == 1 => {
+                            if state.navigation_mode != NavigationMode::FileSystem {
+                                state.print_dir_config.alert = String::from("cannot refresh an archive");
+                            }
+
+                            else {
+                                state.curr_instance.refresh_children();
+                                state.print_dir_config.alert = String::from("refreshed");
+                            }
+                        },
+                        // exits the archive
+                        Some('q') if chars.len() == 1 && state.navigation_mode != NavigationMode::FileSystem => {
+                            if let NavigationMode::Archive { archive, .. } = state.navigation_mode {
+                                debug_assert_uid_valid!(archive);
+                                state.set_curr_uid(get_file_by_uid(archive).unwrap().get_parent_uid());
+                                state.print_dir_config.offset = 0;
+                            }
+                        },
+                        // goes to the parent dir, like `q` in the file viewer
+                        Some('q') if chars.len() == 1 => {
+                            if state.curr_instance.has_parent() {
+                                state.set_curr_uid(state.curr_instance.get_parent_uid());
+                                state.print_dir_config.offset = 0;
+                            }
+                        },
+                        // goes to the root dir
+                        Some('Q') if chars.len() == 1 => {
+                            // `get_parent_uid` registers the ancestors on the way
+                            // on windows, it stops at the root of the drive
+                            while state.curr_instance.has_parent() {
+                                state.set_curr_uid(state.curr_instance.get_parent_uid());
+                            }
+
+                            state.print_dir_config.offset = 0;
+                        },
+                        // special commands, see `commands.rs`
+                        Some(';') => {
+                            run_command(&dir_commands, &mut state, &buffer[1..]);
+                        },
+                        _ => if let Some(uid) = iterate_paths(state.curr_uid, &paths) {
+                            state.set_curr_uid(uid);
+                            state.print_dir_config.offset = 0;
+                        }
+
+                        else if let Some(uid) = search_by_prefix(state.curr_uid, &paths) {
+                            state.set_curr_uid(uid);
+                            state.print_dir_config.offset = 0;
                         }
 
-                        else if let Some(uid) = search_by_prefix(curr_uid, &paths) {
-                            curr_uid = uid;
-                            curr_instance = get_file_by_uid(curr_uid).unwrap();
-                            print_dir_config.offset = 0;
+                        // `<N>` is `;n<N>`, unless there's a file whose name starts with `<N>`
+                        else if !chars.is_empty() && chars.len() <= 4 && chars.iter().all(|c| c.is_ascii_digit()) {
+                            goto_nth_file(&mut state, parse_int_from(&chars) as usize);
                         }
 
                         else {
-                            print_dir_config.alert = format!("{buffer:?} file not found");
+                            state.print_dir_config.alert = format!("{buffer:?} file not found");
                         },
                     }
                 },
@@ -166,10 +248,9 @@ fn main() {
                 FileType::Symlink
                 | FileType::File => {
                     // TODO: better parsing...
-                    let mut buffer = String::new();
-                    io::stdin().read_line(&mut buffer).unwrap();
-                    print_file_config.reset_alert();
-                    print_link_config.reset_alert();
+                    let buffer = read_command(&mut state.history, &|_| vec![], None);
+                    state.print_file_config.reset_alert();
+                    state.print_link_config.reset_alert();
 
                     let jump_by = match previous_print_file_result.viewer_kind {
                         // a line is a line (for texts and images)
@@ -181,96 +262,129 @@ fn main() {
                     };
 
                     let mut has_changed_path = false;
-                    let chars = buffer.strip_suffix("\n").unwrap().to_string().chars().collect::<Vec<char>>();
+                    let chars = buffer.chars().collect::<Vec<char>>();
 
                     match chars.get(0) {
+                        Some(c) if (*c == 'c' || *c == 'C') && chars.len() == 1 => {
+                            let alert = copy_path_to_clipboard(state.curr_uid, *c == 'C');
+
+                            if curr_mode == FileType::Symlink {
+                                state.print_link_config.alert = alert;
+                            } else {
+                                state.print_file_config.alert = alert;
+                            }
+                        },
+                        // special commands, see `commands.rs`
+                        Some(';') => {
+                            let prev_uid = state.curr_uid;
+                            run_command(&file_commands, &mut state, &buffer[1..]);
+                            has_changed_path = state.curr_uid != prev_uid;
+                        },
+                        // toggles the line wrap of the text viewer
+                        Some('w') if chars.len() == 1 && curr_mode == FileType::File => {
+                            state.print_file_config.wrap_lines = !state.print_file_config.wrap_lines;
+                            state.print_file_config.alert = format_wrap_alert(&state.print_file_config);
+                        },
+                        // moves a byte cursor with hjkl (or the arrow keys), until Escape
+                        Some('i') if chars.len() == 1 && curr_mode == FileType::File && matches!(previous_print_file_result.viewer_kind, ViewerKind::Hex) => {
+                            run_hex_cursor_mode(&mut state, previous_print_file_result.width);
+                        },
+                        // cycles through the viewers
+                        Some('v') if chars.len() == 1 => {
+                            state.print_file_config.read_mode = state.print_file_config.read_mode.next();
+                            state.print_file_config.offset = 0;
+                            state.print_file_config.highlights = vec![];
+                            state.print_file_config.search_pattern = None;
+                            state.print_file_config.alert = format!("viewing as: {}", state.print_file_config.read_mode);
+                        },
                         Some('j') => match chars.get(1) {
                             Some('j') => match chars.get(2) {
                                 Some('j') => {  // jjj
-                                    print_file_config.offset += 100 * jump_by;
+                                    state.print_file_config.offset += 100 * jump_by;
                                 },
                                 _ => {  // jj
-                                    print_file_config.offset += 10 * jump_by;
+                                    state.print_file_config.offset += 10 * jump_by;
                                 },
                             },
                             Some(c) if '0' <= *c && *c <= '9' => {
                                 let n = parse_int_from(&chars[1..]) as usize;
-                                print_file_config.offset += n * jump_by;
+                                state.print_file_config.offset += n * jump_by;
                             },
                             _ => {  // j
-                                print_file_config.offset += jump_by;
+                                state.print_file_config.offset += jump_by;
                             },
                         },
                         Some('k') => match chars.get(1) {
                             Some('k') => match chars.get(2) {
                                 Some('k') => {  // kkk
-                                    print_file_config.offset = print_file_config.offset.max(100 * jump_by) - 100 * jump_by;
+                                    state.print_file_config.offset = state.print_file_config.offset.max(100 * jump_by) - 100 * jump_by;
                                 },
                                 _ => {  // kk
-                                    print_file_config.offset = print_file_config.offset.max(10 * jump_by) - 10 * jump_by;
+                                    state.print_file_config.offset = state.print_file_config.offset.max(10 * jump_by) - 10 * jump_by;
                                 },
                             },
                             Some(c) if '0' <= *c && *c <= '9' => {
                                 let n = parse_int_from(&chars[1..]) as usize;
-                                print_file_config.offset = print_file_config.offset.max(n * jump_by) - n * jump_by;
+                                state.print_file_config.offset = state.print_file_config.offset.max(n * jump_by) - n * jump_by;
                             },
                             _ => {  // k
-                                print_file_config.offset = print_file_config.offset.max(jump_by) - jump_by;
+                                state.print_file_config.offset = state.print_file_config.offset.max(jump_by) - jump_by;
                             },
                         },
                         Some('n') => match chars.get(1) {
                             Some('o') => match chars.get(2) {
                                 Some('h') => {
-                                    print_file_config.highlights = vec![];
+                                    state.print_file_config.highlights = vec![];
+                                    state.print_file_config.search_pattern = None;
                                 },
                                 _ => {},
                             },
                             _ => {
-                                if print_file_config.highlights.len() > 0 {
-                                    let new_highlight_index = match print_file_config.highlights.binary_search(&print_file_config.offset) {
-                                        Ok(n) => (n + 1) % print_file_config.highlights.len(),
-                                        Err(n) => n % print_file_config.highlights.len(),
+                                if state.print_file_config.highlights.len() > 0 {
+                                    let new_highlight_index = match state.print_file_config.highlights.binary_search(&state.print_file_config.offset) {
+                                        Ok(n) => (n + 1) % state.print_file_config.highlights.len(),
+                                        Err(n) => n % state.print_file_config.highlights.len(),
                                     };
     
-                                    print_file_config.offset = print_file_config.highlights[new_highlight_index];
-                                    print_file_config.alert = format!("search result {}/{}", new_highlight_index + 1, print_file_config.highlights.len());
+                                    state.print_file_config.offset = state.print_file_config.highlights[new_highlight_index];
+                                    state.print_file_config.alert = format!("search result {}/{}", new_highlight_index + 1, state.print_file_config.highlights.len());
                                 }
                             },
                         },
-                        Some('N') if print_file_config.highlights.len() > 0 => {
-                            let new_highlight_index = match print_file_config.highlights.binary_search(&print_file_config.offset) {
-                                Ok(n) => (n + print_file_config.highlights.len() - 1) % print_file_config.highlights.len(),
-                                Err(n) => (n + print_file_config.highlights.len() - 1) % print_file_config.highlights.len(),
+                        Some('N') if state.print_file_config.highlights.len() > 0 => {
+                            let new_highlight_index = match state.print_file_config.highlights.binary_search(&state.print_file_config.offset) {
+                                Ok(n) => (n + state.print_file_config.highlights.len() - 1) % state.print_file_config.highlights.len(),
+                                Err(n) => (n + state.print_file_config.highlights.len() - 1) % state.print_file_config.highlights.len(),
                             };
 
-                            print_file_config.offset = print_file_config.highlights[new_highlight_index];
-                            print_file_config.alert = format!("search result {}/{}", new_highlight_index + 1, print_file_config.highlights.len());
+                            state.print_file_config.offset = state.print_file_config.highlights[new_highlight_index];
+                            state.print_file_config.alert = format!("search result {}/{}", new_highlight_index + 1, state.print_file_config.highlights.len());
                         },
                         Some('G') => {
                             match previous_print_file_result.viewer_kind {
                                 ViewerKind::Text
                                 | ViewerKind::Image => {
-                                    print_file_config.offset = previous_print_file_result.last_line.unwrap_or(1).max(1) - 1;
+                                    state.print_file_config.offset = previous_print_file_result.last_line.unwrap_or(1).max(1) - 1;
                                 },
                                 ViewerKind::Hex => {
-                                    print_file_config.offset = (curr_instance.size as usize).max(1) - 1;
+                                    state.print_file_config.offset = (state.curr_instance.size as usize).max(1) - 1;
                                 },
                             }
                         },
                         Some('g') => match chars.get(1) {
                             Some('g') => {
-                                print_file_config.offset = 0;
+                                state.print_file_config.offset = 0;
                             },
                             _ => {},
                         },
                         Some('0') => match chars.get(1) {
                             Some('x') | Some('X') if chars.len() > 2 => {
                                 let n = parse_hex_from(&chars[2..]);
-                                print_file_config.offset = n as usize;
+                                state.print_file_config.offset = n as usize;
                             },
                             _ => {
                                 let n = parse_int_from(&chars[0..]);
-                                print_file_config.offset = n as usize;
+                                state.print_file_config.offset = n as usize;
                             },
                         },
                         Some('s') => match chars.get(1) {
@@ -278,7 +392,7 @@ fn main() {
                                 Some('t') => match chars.get(3) {
                                     Some(' ') => match parse_kw_args(&chars[3..]) {
                                         Some((k, v)) => if k == "syntax" {
-                                            print_file_config.syntax_highlight = Some(v.to_string());
+                                            state.print_file_config.syntax_highlight = Some(v.to_string());
                                         } else {
                                             // todo: error
                                         },
@@ -292,22 +406,40 @@ fn main() {
                         }
                         Some(c) if '1' <= *c && *c <= '9' => {
                             let n = parse_int_from(&chars[0..]);
-                            print_file_config.offset = n as usize;
+                            state.print_file_config.offset = n as usize;
                         },
                         Some('q') => {
                             has_changed_path = true;
-                            curr_uid = curr_instance.get_parent_uid();
-                            curr_instance = get_file_by_uid(curr_uid).unwrap();
+                            state.set_curr_uid(state.curr_instance.get_parent_uid());
+                        },
+                        // `/0xAB CD EF` or `/ELF` searches the bytes, and `highlights` are the byte offsets
+                        Some('/') if matches!(previous_print_file_result.viewer_kind, ViewerKind::Hex) => {
+                            let offsets = parse_byte_pattern(&chars[1..].iter().collect::<String>()).and_then(
+                                |needle| find_byte_sequence(state.curr_uid, &needle)
+                            );
+
+                            match offsets {
+                                Some(offsets) => {
+                                    state.print_file_config.alert = format!("found {} matches", offsets.len());
+                                    state.print_file_config.highlights = offsets;
+                                },
+                                None => {
+                                    state.print_file_config.alert = String::from("search failed");
+                                    state.print_file_config.highlights = vec![];
+                                },
+                            }
+
+                            state.print_file_config.search_pattern = None;
                         },
-                        // TODO: search feature in hex viewer
                         Some('/') => {  // TODO: it's very naive implementation
                             let mut matched_lines = vec![];
+                            let mut search_pattern = None;
                             let mut search_error = true;
 
                             if chars.len() > 2 {
                                 // [1..] excludes '/'
                                 if let Ok(re) = Regex::new(&chars[1..].iter().collect::<String>()) {
-                                    if let Some(path) = get_path_by_uid(curr_uid) {
+                                    if let Some(path) = try_get_path_by_uid(state.curr_uid) {
                                         if let Ok(file) = fs::File::open(path) {
                                             let line_reader = BufReader::new(file);
                                             search_error = false;
@@ -321,28 +453,30 @@ fn main() {
                                             }
                                         }
                                     }
+
+                                    search_pattern = Some(re);
                                 }
                             }
 
                             if search_error {
-                                print_file_config.alert = String::from("search failed");
+                                state.print_file_config.alert = String::from("search failed");
                             }
 
                             else {
-                                print_file_config.alert = format!("found {} results", matched_lines.len());
+                                state.print_file_config.alert = format!("found {} results", matched_lines.len());
                             }
 
-                            print_file_config.highlights = matched_lines;
+                            state.print_file_config.highlights = matched_lines;
+                            state.print_file_config.search_pattern = search_pattern;
                         },
                         Some('.') => match chars.get(1) {
                             Some('.') => {  // for convenience, `..` is an alias for `q`
-                                print_file_config.offset = 0;
+                                state.print_file_config.offset = 0;
 
                                 for ch in chars[1..].iter() {
-                                    if *ch == '.' && curr_uid != Uid::ROOT {
+                                    if *ch == '.' && state.curr_instance.has_parent() {
                                         has_changed_path = true;
-                                        curr_uid = curr_instance.get_parent_uid();
-                                        curr_instance = get_file_by_uid(curr_uid).unwrap();
+                                        state.set_curr_uid(state.curr_instance.get_parent_uid());
                                     }
 
                                     else {
@@ -356,71 +490,257 @@ fn main() {
                     }
 
                     if has_changed_path {
-                        print_file_config.offset = 0;
-                        print_file_config.highlights = vec![];
-                        print_file_config.read_mode = FileReadMode::default();
-                        print_file_config.syntax_highlight = None;
+                        state.print_file_config.offset = 0;
+                        state.print_file_config.highlights = vec![];
+                        state.print_file_config.search_pattern = None;
+                        state.print_file_config.read_mode = FileReadMode::default();
+                        state.print_file_config.syntax_highlight = None;
                     }
 
                     else {
                         // TODO: it has to do the same thing to dir_config
                         if let Some(line_no) = previous_print_file_result.last_line {
-                            if print_file_config.offset >= line_no {
-                                print_file_config.offset = line_no.max(1) - 1;
+                            if state.print_file_config.offset >= line_no {
+                                state.print_file_config.offset = line_no.max(1) - 1;
                             }
                         }
                     }
                 },
+                // the main loop never sets `curr_mode` to the special files
+                #[cfg(unix)]
+                FileType::Device
+                | FileType::Fifo
+                | FileType::Socket
+                | FileType::Unknown => unreachable!(),
             }
 
-            print_dir_config.adjust_output_dimension();
-            print_file_config.adjust_output_dimension();
-            print_link_config.adjust_output_dimension();
+            // `..` and `q` might have left the archive
+            if state.navigation_mode != NavigationMode::FileSystem && !is_archive_member(state.curr_uid) {
+                state.navigation_mode = NavigationMode::FileSystem;
+            }
 
-            while print_dir_config.max_width < 40 {
+            // the results of `;G` belong to the dir where it's run
+            if let Some((grep_dir, _)) = &state.print_dir_config.grep {
+                if *grep_dir != state.curr_uid && get_file_by_uid(state.curr_uid).map(|f| f.is_dir()).unwrap_or(false) {
+                    clear_grep_results(&mut state.print_dir_config);
+                }
+            }
+
+            state.print_dir_config.adjust_output_dimension();
+            state.print_file_config.adjust_output_dimension();
+            state.print_link_config.adjust_output_dimension();
+
+            while state.print_dir_config.max_width < 40 {
                 println!("Your terminal is too small to run FileQuery. Please resize your terminal and try again.");
                 thread::sleep(time::Duration::from_millis(300));
 
-                print_dir_config.adjust_output_dimension();
-                print_file_config.adjust_output_dimension();
-                print_link_config.adjust_output_dimension();
+                state.print_dir_config.adjust_output_dimension();
+                state.print_file_config.adjust_output_dimension();
+                state.print_link_config.adjust_output_dimension();
                 clearscreen::clear().unwrap();
             }
 
             unsafe { IS_MASTER_WORKING = true; }
 
-            match get_file_by_uid(curr_uid) {
+            collect_work_results();
+
+            match get_file_by_uid(state.curr_uid) {
                 Some(f) => match f.file_type {
                     FileType::Dir => {
-                        previous_print_dir_result = print_dir(curr_uid, &print_dir_config);
+                        previous_print_dir_result = print_dir(state.curr_uid, &state.print_dir_config);
                         curr_mode = FileType::Dir;
                     },
                     FileType::File => {
-                        previous_print_file_result = print_file(curr_uid, &print_file_config);
+                        // a result of `;G` is opened with its matched lines highlighted
+                        if curr_mode == FileType::Dir && get_match_count(state.curr_uid).is_some() {
+                            if let Some((_, pattern)) = &state.print_dir_config.grep {
+                                state.print_file_config.highlights = find_matched_lines(state.curr_uid, pattern).unwrap_or(vec![]);
+                                state.print_file_config.search_pattern = Some(pattern.clone());
+                                state.print_file_config.offset = state.print_file_config.highlights.first().copied().unwrap_or(0);
+                            }
+                        }
+
+                        previous_print_file_result = print_file(state.curr_uid, &state.print_file_config);
                         curr_mode = FileType::File;
                     },
                     FileType::Symlink => {
-                        previous_print_link_result = print_link(curr_uid, &print_link_config);
+                        previous_print_link_result = print_link(state.curr_uid, &state.print_link_config);
                         curr_mode = FileType::Symlink;
                     },
+                    // `print_file` would block on a fifo, and the others don't have contents to show
+                    #[cfg(unix)]
+                    FileType::Device
+                    | FileType::Fifo
+                    | FileType::Socket
+                    | FileType::Unknown => {
+                        state.print_dir_config.alert = format!("cannot open a {}", f.file_type);
+                        state.set_curr_uid(f.get_parent_uid());
+                        previous_print_dir_result = print_dir(state.curr_uid, &state.print_dir_config);
+                        curr_mode = FileType::Dir;
+                    },
                 },
                 None => {
                     print_error_message(
-                        Some(curr_instance),
+                        Some(state.curr_instance),
                         None,
-                        format!("get_file_by_uid({}) has failed", curr_uid.debug_info()),
-                        print_dir_config.min_width,
-                        print_dir_config.max_width,
+                        FileQueryError::FileNotFound(state.curr_uid),
+                        state.print_dir_config.min_width,
+                        state.print_dir_config.max_width,
                     );
                 },
             }
 
             flip_buffer(is_interactive_mode);
+            save_session();
             unsafe { IS_MASTER_WORKING = false; }
         }
     }
 }
 
+// the workers' results (e.g. `ColumnKind::TotalSize`) are shown without waiting for the next command
+// it returns true if it has redrawn the screen
+fn redraw_dir_on_work_results(uid: Uid, print_dir_config: &PrintDirConfig) -> bool {
+    if collect_work_results() == 0 {
+        return false;
+    }
+
+    print_dir(uid, print_dir_config);
+    flip_buffer(true);
+    true
+}
+
+fn format_wrap_alert(print_file_config: &PrintFileConfig) -> String {
+    match (print_file_config.wrap_lines, print_file_config.wrap_column) {
+        (false, _) => String::from("line wrap off"),
+        (true, Some(n)) => format!("wrap at {n} columns"),
+        (true, None) => String::from("wrap at the terminal width"),
+    }
+}
+
+// an empty `NO_COLOR` doesn't count
+fn is_no_color_set() -> bool {
+    std::env::var("NO_COLOR").map(|v| !v.is_empty()).unwrap_or(false)
+}
+
+fn clear_grep_results(print_dir_config: &mut PrintDirConfig) {
+    print_dir_config.grep = None;
+    print_dir_config.columns.retain(|col| !matches!(col, ColumnKind::MatchCount));
+    set_grep_results(&[]);
+}
+
+// the sibling right after (or before) `file`, in the order of `print_dir`
+fn get_adjacent_sibling(file: &File, print_dir_config: &PrintDirConfig, forward: bool) -> Option<Uid> {
+    let mut siblings = file.get_siblings(print_dir_config.show_hidden_files);
+    siblings.retain(|sibling| !sibling.is_special_file());
+    sort_files(&mut siblings, print_dir_config.sort_by, print_dir_config.sort_reverse);
+
+    let index = siblings.iter().position(|sibling| sibling.uid == file.uid)?;
+    let next_index = if forward { index + 1 } else { index.checked_sub(1)? };
+
+    siblings.get(next_index).map(|sibling| sibling.uid)
+}
+
+// the target of the link, which is registered if it's not yet
+// it follows only one link, so the target might be another link
+// the error is a message for the alert
+fn follow_symlink(uid: Uid) -> Result<Uid, String> {
+    let path = try_get_path_by_uid(uid).ok_or(format!("get_path_by_uid({uid}) has failed"))?;
+    let link = fs::read_link(path).map_err(|e| format!("cannot read the link: {e}"))?;
+
+    // a relative target is relative to the dir of the link
+    let target = PathBuf::from(path).parent().map(|dir| dir.join(&link)).unwrap_or(link);
+
+    if fs::symlink_metadata(&target).is_err() {
+        return Err(format!("broken link: {} doesn't exist", target.to_string_lossy()));
+    }
+
+    // `a/../b` -> `b`, but it keeps the last component as it is, which might be a link
+    let target = match (target.parent().map(fs::canonicalize), target.file_name()) {
+        (Some(Ok(dir)), Some(name)) => dir.join(name),
+        _ => target,
+    };
+    let target_path = target.to_string_lossy().to_string();
+
+    match get_uid_by_path(&target_path) {
+        Some(uid) => Ok(uid),
+        None => Ok(File::new_from_path_buf(target, Some(Uid::from_path(&target_path)), None)),
+    }
+}
+
+// it returns a message for the alert
+// if the clipboard is not available, it prints the path to stderr
+fn copy_path_to_clipboard(uid: Uid, shell_quoted: bool) -> String {
+    let path = match try_get_path_by_uid(uid) {
+        Some(path) if shell_quoted => shell_quote(path),
+        Some(path) => path.to_string(),
+        None => {
+            return format!("get_path_by_uid({uid}) has failed");
+        },
+    };
+
+    match copy_to_clipboard(&path) {
+        Ok(()) => String::from("path copied to clipboard"),
+        Err(e) => {
+            eprintln!("{path}\n(failed to copy the path to clipboard: {e})");
+            e
+        },
+    }
+}
+
+// the hex viewer renders itself on every key press, and the main loop renders it again after Escape
+fn run_hex_cursor_mode(state: &mut AppState, bytes_per_row: usize) {
+    let size = state.curr_instance.size;
+
+    if size == 0 {
+        state.print_file_config.alert = String::from("empty file");
+        return;
+    }
+
+    let bytes_per_row = bytes_per_row.max(1) as u64;
+    let mut cursor = (state.print_file_config.offset as u64).min(size - 1);
+
+    loop {
+        // `print_file` shows `max_row + 1` rows, from the offset rounded down to a multiple of 8 (but not after `size - 32`)
+        let offset = state.print_file_config.offset as u64;
+        let first_byte = ((offset & !7) + 32).min(size).max(32) - 32;
+        let last_byte = first_byte + (state.print_file_config.max_row as u64 + 1) * bytes_per_row;
+
+        if cursor < first_byte {
+            state.print_file_config.offset = cursor as usize;
+        }
+
+        else if cursor >= last_byte {
+            state.print_file_config.offset = (cursor - (cursor - first_byte) % bytes_per_row).saturating_sub(state.print_file_config.max_row as u64 * bytes_per_row) as usize;
+        }
+
+        state.print_file_config.hex_cursor = Some(cursor);
+        state.print_file_config.elapsed_timer = time::Instant::now();
+        print_file(state.curr_uid, &state.print_file_config);
+        flip_buffer(true);
+
+        match read_key() {
+            Some('j') if cursor + bytes_per_row < size => {
+                cursor += bytes_per_row;
+            },
+            Some('k') if cursor >= bytes_per_row => {
+                cursor -= bytes_per_row;
+            },
+            Some('l') if cursor + 1 < size => {
+                cursor += 1;
+            },
+            Some('h') if cursor > 0 => {
+                cursor -= 1;
+            },
+            Some(_) => {},
+            None => {
+                break;
+            },
+        }
+    }
+
+    state.print_file_config.hex_cursor = None;
+}
+
 // TODO: these should not belong to `main.rs`
 fn parse_int_from(chars: &[char]) -> u64 {
     let mut result = 0;
@@ -0,0 +1,92 @@
+use crate::print::PrintDirConfig;
+use crate::utils::get_config_path;
+use std::fs;
+use toml::{Table, Value};
+use toml_edit::DocumentMut;
+
+// preferences that the user has saved with commands like `;h`
+// they're persisted to `~/.config/file_query/config.toml`, and are applied at launch
+
+// it returns an empty table if there's no config file, or if it's broken
+fn load_table() -> Table {
+    try_load_table().unwrap_or_default()
+}
+
+// it's an error only if the config file exists and is broken
+fn try_load_table() -> Result<Table, String> {
+    match get_config_path("config.toml").map(fs::read_to_string) {
+        Some(Ok(s)) => s.parse::<Table>().map_err(|e| e.message().to_string()),
+        _ => Ok(Table::new()),
+    }
+}
+
+// it overwrites the fields of `print_dir_config` with the saved preferences
+// unknown keys and values of wrong types are ignored
+pub fn load_preferences(print_dir_config: &mut PrintDirConfig) {
+    apply_table(&load_table(), print_dir_config);
+}
+
+// it's like `load_preferences`, but it keeps `print_dir_config` untouched if the config file is broken
+// the error message is from the toml parser
+pub fn reload_preferences(print_dir_config: &mut PrintDirConfig) -> Result<(), String> {
+    apply_table(&try_load_table()?, print_dir_config);
+    Ok(())
+}
+
+// a key that's not in the table goes back to its default value, so that deleting a key and reloading works
+fn apply_table(table: &Table, print_dir_config: &mut PrintDirConfig) {
+    let default = PrintDirConfig::default();
+    print_dir_config.show_hidden_files = default.show_hidden_files;
+    print_dir_config.external_file_manager = default.external_file_manager;
+
+    if let Some(Value::Boolean(b)) = table.get("show_hidden_files") {
+        print_dir_config.show_hidden_files = *b;
+    }
+
+    if let Some(Value::String(s)) = table.get("external_file_manager") {
+        print_dir_config.external_file_manager = Some(s.to_string());
+    }
+}
+
+// it saves the config file immediately, keeping the other preferences and the user's comments
+// it refuses to write if the config file is broken, so that a typo doesn't wipe the whole file
+pub fn save_preference(key: &str, value: Value) -> Result<(), String> {
+    let path = match get_config_path("config.toml") {
+        Some(path) => path,
+        None => {
+            return Err(String::from("$HOME is not available"));
+        },
+    };
+
+    let mut document = match fs::read_to_string(&path) {
+        Ok(s) => s.parse::<DocumentMut>().map_err(|e| format!("config.toml is broken ({})", e.message().trim().replace('\n', ", ")))?,
+        Err(_) => DocumentMut::new(),
+    };
+    let value = value.to_string().parse::<toml_edit::Value>().map_err(|e| e.to_string())?;
+    document[key] = toml_edit::value(value);
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    fs::write(path, document.to_string()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_table_resets_deleted_keys() {
+        let mut config = PrintDirConfig::default();
+        apply_table(&"show_hidden_files = true\nexternal_file_manager = \"nnn\"".parse().unwrap(), &mut config);
+
+        assert!(config.show_hidden_files);
+        assert_eq!(config.external_file_manager.as_deref(), Some("nnn"));
+
+        apply_table(&Table::new(), &mut config);
+
+        assert_eq!(config.show_hidden_files, PrintDirConfig::default().show_hidden_files);
+        assert_eq!(config.external_file_manager, None);
+    }
+}
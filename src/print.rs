@@ -1,25 +1,50 @@
-use colored::{Color, Colorize};
+use colored::{Color, ColoredString, Colorize};
 use crate::colors;
+use crate::error::FileQueryError;
 use crate::file::File;
 use std::collections::{HashMap, HashSet};
 
+static mut SCREEN_BUFFER: Vec<String> = Vec::new();
+
+// the submodules share these, so they have to be defined before the `mod` declarations
+macro_rules! print_to_buffer {
+    ($($arg:tt)*) => {
+        unsafe {
+            (*std::ptr::addr_of_mut!($crate::print::SCREEN_BUFFER)).push(format!($($arg)*));
+        }
+    };
+}
+
+macro_rules! println_to_buffer {
+    ($($arg:tt)*) => {
+        print_to_buffer!($($arg)*);
+        print_to_buffer!("\n");
+    };
+}
+
 mod config;
 mod dir;
+mod duplicates;
 mod file;
 mod link;
 mod result;
+mod stat;
 mod utils;
 
 const COLUMN_MARGIN: usize = 2;
 
 pub use config::{
     ColumnKind,
+    FileEncoding,
     FileReadMode,
+    PermissionsFormat,
     PrintDirConfig,
     PrintFileConfig,
     PrintLinkConfig,
+    SYNTAX_THEMES,
 };
-pub use dir::print_dir;
+pub use dir::{get_visible_children, print_dir};
+pub use duplicates::{print_duplicates, DuplicateGroup};
 pub use file::print_file;
 pub use link::print_link;
 pub use result::{
@@ -28,24 +53,10 @@ pub use result::{
     PrintLinkResult,
     ViewerKind,
 };
+pub use stat::print_stat;
 use utils::split_long_str;
 
-static mut SCREEN_BUFFER: Vec<String> = Vec::new();
-
-macro_rules! print_to_buffer {
-    ($($arg:tt)*) => {
-        unsafe {
-            SCREEN_BUFFER.push(format!($($arg)*));
-        }
-    };
-}
-
-// macro_rules! println_to_buffer {
-//     ($($arg:tt)*) => {
-//         print_to_buffer!($($arg)*);
-//         print_to_buffer!("\n");
-//     };
-// }
+pub(crate) use utils::try_extract_utf8_text;
 
 #[derive(Clone)]
 pub enum Alignment {
@@ -55,7 +66,7 @@ pub enum Alignment {
 pub fn print_error_message(
     file: Option<&File>,
     path: Option<String>,
-    message: String,
+    error: FileQueryError,
     min_width: usize,
     max_width: usize,
 ) {
@@ -83,9 +94,15 @@ pub fn print_error_message(
         }
     }
 
-    for (index, line) in split_long_str(message).into_iter().enumerate() {
+    rows.push(vec![
+        String::from("kind"),
+        String::from("│"),
+        error.kind(),
+    ]);
+
+    for (index, line) in split_long_str(error.details()).into_iter().enumerate() {
         rows.push(vec![
-            if index == 0 { String::from("message") } else { String::new() },
+            if index == 0 { String::from("details") } else { String::new() },
             String::from("│"),
             line,
         ]);
@@ -104,21 +121,7 @@ pub fn print_error_message(
         table_width + COLUMN_MARGIN * 2,
         (true, false),
         (true, true),
-    );
-    print_row(
-        colors::BLACK,
-        &vec![String::from("error")],
-        &vec![table_width],
-        &vec![Alignment::Center],
-        &vec![LineColor::All(colors::WHITE)],
-        COLUMN_MARGIN,
-        (true, true),
-    );
-    print_horizontal_line(
-        None,
-        table_width + COLUMN_MARGIN * 2,
-        (false, false),
-        (true, true),
+        Some("error"),
     );
 
     for row in rows.iter() {
@@ -138,16 +141,30 @@ pub fn print_error_message(
         table_width + COLUMN_MARGIN * 2,
         (false, true),
         (true, true),
+        None,
     );
 }
 
 // you can either
 // 1. color the entire line with the same color
 // 2. color each character
+// 3. color each character, and highlight some ranges with background colors
+//    ranges are (start, end) byte offsets of the content (`end` is exclusive)
 #[derive(Clone)]
 pub enum LineColor {
     All(Color),
     Each(Vec<Color>),
+    Highlight(Vec<Color>, Vec<(usize, usize, Color)>),
+}
+
+fn get_highlight_color(ranges: &[(usize, usize, Color)], byte_index: usize) -> Option<Color> {
+    for (start, end, color) in ranges.iter() {
+        if *start <= byte_index && byte_index < *end {
+            return Some(*color);
+        }
+    }
+
+    None
 }
 
 fn print_row(
@@ -208,6 +225,27 @@ fn print_row(
                         parts.push(ch.to_string().color(colors[idx]));
                     }
 
+                    // default color
+                    parts.push(" ".repeat(right_margin).color(colors::WHITE));
+                },
+                LineColor::Highlight(colors, ranges) => {
+                    debug_assert_eq!(
+                        curr_content_len,
+                        colors.len(),
+                    );
+
+                    // default color
+                    parts.push(" ".repeat(left_margin).color(colors::WHITE));
+
+                    for (idx, (byte_idx, ch)) in contents[i].char_indices().enumerate() {
+                        let part = ch.to_string().color(colors[idx]);
+
+                        match get_highlight_color(ranges, byte_idx) {
+                            Some(c) => parts.push(part.on_color(c)),
+                            None => parts.push(part),
+                        }
+                    }
+
                     // default color
                     parts.push(" ".repeat(right_margin).color(colors::WHITE));
                 },
@@ -247,11 +285,43 @@ fn print_row(
                         parts.push(suffix[i].to_string().color(suffix_colors[i]));
                     }
                 },
+                LineColor::Highlight(colors, ranges) => {
+                    debug_assert_eq!(
+                        curr_content_len,
+                        colors.len(),
+                    );
+
+                    let byte_indices = contents[i].char_indices().map(|(byte_idx, _)| byte_idx).collect::<Vec<_>>();
+                    let suffix_start = curr_content_len - last_half;
+
+                    for i in 0..prefix.len() {
+                        let part = prefix[i].to_string().color(colors[i]);
+
+                        match get_highlight_color(ranges, byte_indices[i]) {
+                            Some(c) => parts.push(part.on_color(c)),
+                            None => parts.push(part),
+                        }
+                    }
+
+                    parts.push("...".color(colors::WHITE));
+
+                    for i in 0..suffix.len() {
+                        let part = suffix[i].to_string().color(colors[suffix_start + i]);
+
+                        match get_highlight_color(ranges, byte_indices[suffix_start + i]) {
+                            Some(c) => parts.push(part.on_color(c)),
+                            None => parts.push(part),
+                        }
+                    }
+                },
             }
         }
 
         for part in parts.into_iter() {
-            print_to_buffer!("{}", part.on_color(background));
+            // highlighted parts already have their own background colors
+            let part = if part.bgcolor.is_some() { part } else { part.on_color(background) };
+
+            print_to_buffer!("{part}");
         }
 
         print_to_buffer!(
@@ -274,6 +344,7 @@ fn print_horizontal_line(
     width: usize,
     vertical_position: (bool, bool),  // (is top, is bottom)
     borders: (bool, bool),  // (left, right)
+    title: Option<&str>,  // `──── title ────`
 ) {
     if borders.0 {  // left border
         if vertical_position.0 {  // is top
@@ -289,12 +360,33 @@ fn print_horizontal_line(
         }
     }
 
-    if let Some(c) = background {
-        print_to_buffer!("{}", "─".repeat(width).on_color(c));
-    }
+    // there must be at least 1 dash at each side of the title
+    match title {
+        Some(title) if width >= 7 => {
+            let title_len = title.chars().count();
+            let title = if title_len > width - 4 {
+                format!("{}...", title.chars().take(width - 7).collect::<String>())
+            } else {
+                title.to_string()
+            };
+            let title_len = title.chars().count() + 2;  // spaces around the title
+            let left_width = (width - title_len) >> 1;
+            let right_width = width - title_len - left_width;
+            let title = format!(" {title} ").color(colors::WHITE);
+            let title = match background {
+                Some(c) => title.on_color(c),
+                None => title,
+            };
 
-    else {
-        print_to_buffer!("{}", "─".repeat(width));
+            print_to_buffer!(
+                "{}{title}{}",
+                render_dashes(background, left_width),
+                render_dashes(background, right_width),
+            );
+        },
+        _ => {
+            print_to_buffer!("{}", render_dashes(background, width));
+        },
     }
 
     if borders.1 {  // right border
@@ -314,6 +406,13 @@ fn print_horizontal_line(
     print_to_buffer!("\n");
 }
 
+fn render_dashes(background: Option<Color>, width: usize) -> ColoredString {
+    match background {
+        Some(c) => "─".repeat(width).on_color(c),
+        None => "─".repeat(width).normal(),
+    }
+}
+
 // it has some odd rules to follow...
 // Let's say a row has 1 ~ M columns (1 <= M).
 // 1. The first row must have M columns.
@@ -329,6 +428,11 @@ fn calc_table_column_widths(
         assert!(t >= m);
     }
 
+    // the call sites expect at least one entry
+    if table_contents.first().map(|row| row.is_empty()).unwrap_or(true) {
+        return HashMap::from([(0, vec![])]);
+    }
+
     let mut max_column_widths = table_contents[0].iter().map(|c| c.chars().count()).collect::<Vec<_>>();
     let mut col_counts = HashSet::new();
     col_counts.insert(table_contents[0].len());
@@ -344,7 +448,7 @@ fn calc_table_column_widths(
         }
 
         else {
-            for i in 0..(curr_row_widths.len() - 1) {
+            for i in 0..curr_row_widths.len().saturating_sub(1) {
                 max_column_widths[i] = max_column_widths[i].max(curr_row_widths[i]);
             }
         }
@@ -392,6 +496,11 @@ fn calc_table_column_widths(
     let mut result = HashMap::with_capacity(col_counts.len());
 
     for col_count in col_counts.into_iter() {
+        if col_count == 0 {
+            result.insert(0, vec![]);
+            continue;
+        }
+
         let mut widths = Vec::with_capacity(col_count);
         let mut curr_total_width = 0;
 
@@ -416,11 +525,43 @@ pub fn flip_buffer(clear_screen: bool) {
         clearscreen::clear().unwrap();
     }
 
-    unsafe {
-        for s in SCREEN_BUFFER.iter() {
-            print!("{s}");
-        }
+    let buffer = unsafe { &mut *std::ptr::addr_of_mut!(SCREEN_BUFFER) };
+
+    for s in buffer.iter() {
+        print!("{s}");
+    }
+
+    buffer.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calc_table_column_widths_with_empty_first_row() {
+        let widths = calc_table_column_widths(&vec![vec![]], Some(80), Some(40), 2);
+        assert_eq!(widths, HashMap::from([(0, vec![])]));
+
+        let widths = calc_table_column_widths(&vec![vec![], vec![String::from("abc")]], Some(80), None, 2);
+        assert_eq!(widths, HashMap::from([(0, vec![])]));
+
+        let widths = calc_table_column_widths(&vec![], None, None, 2);
+        assert_eq!(widths, HashMap::from([(0, vec![])]));
+    }
+
+    #[test]
+    fn calc_table_column_widths_with_rowspan() {
+        let table = vec![
+            vec![String::from("a"), String::from("bb"), String::from("ccc")],
+            vec![String::from("aaaa"), String::from("b"), String::from("c")],
+            vec![String::from("a"), String::from("b")],
+        ];
+        let widths = calc_table_column_widths(&table, None, None, 1);
+
+        assert_eq!(widths[&3], vec![4, 2, 3]);
 
-        SCREEN_BUFFER.clear();
+        // the last column spans the last 2 columns, including the margin between them
+        assert_eq!(widths[&2], vec![4, 6]);
     }
 }
@@ -1,9 +1,14 @@
-use super::Alignment;
+use super::{Alignment, COLUMN_MARGIN};
 use super::result::ViewerKind;
+use colored::Color;
+use crate::colors;
+use crate::uid::Uid;
+use regex::Regex;
+use std::fmt;
 use std::time::Instant;
 use terminal_size::{self as ts, terminal_size};
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq)]
 pub enum ColumnKind {
     Index,
     Name,
@@ -12,6 +17,39 @@ pub enum ColumnKind {
     Modified,
     FileType,
     FileExt,
+    Checksum,
+    RelativePath,
+    Depth,
+    Hardness,
+    Delta,
+    Age,
+    WordCount,
+
+    // number of the entries of a dir
+    // in the tree mode, it counts only the files, since the dirs are already shown
+    EntryCount,
+
+    Preview,
+    SymlinkTarget,
+    Birthtime,
+    LastOpened,
+
+    // number of matched lines of `;G`
+    MatchCount,
+
+    // `;P` toggles the format
+    Permissions(PermissionsFormat),
+
+    // color tag of `;tag`
+    // `print_dir` always shows it as the last column
+    Color,
+
+    // `net` if the file is on a network file system
+    NetworkMount,
+
+    // the xattr of the key, set by `;x <key> <value>`
+    // the key is interned by `intern_xattr_key`, so that `ColumnKind` can be `Copy`
+    CustomTag(&'static str),
 }
 
 impl ColumnKind {
@@ -24,6 +62,23 @@ impl ColumnKind {
             ColumnKind::Modified => "modified",
             ColumnKind::FileType => "type",
             ColumnKind::FileExt => "extension",
+            ColumnKind::Checksum => "sha256",
+            ColumnKind::RelativePath => "relative path",
+            ColumnKind::Depth => "depth",
+            ColumnKind::Hardness => "hard link",
+            ColumnKind::Delta => "delta",
+            ColumnKind::Age => "age",
+            ColumnKind::WordCount => "word count",
+            ColumnKind::EntryCount => "entries",
+            ColumnKind::Preview => "preview",
+            ColumnKind::SymlinkTarget => "link target",
+            ColumnKind::Birthtime => "created",
+            ColumnKind::LastOpened => "last opened",
+            ColumnKind::MatchCount => "matches",
+            ColumnKind::Permissions(_) => "permissions",
+            ColumnKind::Color => "tag",
+            ColumnKind::NetworkMount => "net",
+            ColumnKind::CustomTag(key) => key,
         }.to_string()
     }
 
@@ -36,6 +91,23 @@ impl ColumnKind {
             ColumnKind::Modified => "modified",
             ColumnKind::FileType => "type",
             ColumnKind::FileExt => "extension",
+            ColumnKind::Checksum => "checksum",
+            ColumnKind::RelativePath => "relative_path",
+            ColumnKind::Depth => "depth",
+            ColumnKind::Hardness => "hard_link_group",
+            ColumnKind::Delta => "delta",
+            ColumnKind::Age => "age",
+            ColumnKind::WordCount => "word_count",
+            ColumnKind::EntryCount => "entry_count",
+            ColumnKind::Preview => "preview",
+            ColumnKind::SymlinkTarget => "symlink_target",
+            ColumnKind::Birthtime => "birthtime",
+            ColumnKind::LastOpened => "last_opened",
+            ColumnKind::MatchCount => "match_count",
+            ColumnKind::Permissions(_) => "permissions",
+            ColumnKind::Color => "tag",
+            ColumnKind::NetworkMount => "is_network_mount",
+            ColumnKind::CustomTag(key) => key,
         }.to_string()
     }
 
@@ -48,23 +120,121 @@ impl ColumnKind {
             ColumnKind::Modified => Alignment::Right,
             ColumnKind::FileType => Alignment::Left,
             ColumnKind::FileExt => Alignment::Left,
+            ColumnKind::Checksum => Alignment::Left,
+            ColumnKind::RelativePath => Alignment::Left,
+            ColumnKind::Depth => Alignment::Right,
+            ColumnKind::Hardness => Alignment::Left,
+            ColumnKind::Delta => Alignment::Right,
+            ColumnKind::Age => Alignment::Right,
+            ColumnKind::WordCount => Alignment::Right,
+            ColumnKind::EntryCount => Alignment::Right,
+            ColumnKind::Preview => Alignment::Left,
+            ColumnKind::SymlinkTarget => Alignment::Left,
+            ColumnKind::Birthtime => Alignment::Right,
+            ColumnKind::LastOpened => Alignment::Right,
+            ColumnKind::MatchCount => Alignment::Right,
+            ColumnKind::Permissions(PermissionsFormat::Symbolic) => Alignment::Left,
+            ColumnKind::Permissions(PermissionsFormat::Octal) => Alignment::Right,
+            ColumnKind::Color => Alignment::Center,
+            ColumnKind::NetworkMount => Alignment::Center,
+            ColumnKind::CustomTag(_) => Alignment::Left,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PermissionsFormat {
+    Symbolic,  // `rwxr-xr--`
+    Octal,     // `754`
+}
+
+impl PermissionsFormat {
+    pub fn toggle(&self) -> Self {
+        match self {
+            PermissionsFormat::Symbolic => PermissionsFormat::Octal,
+            PermissionsFormat::Octal => PermissionsFormat::Symbolic,
         }
     }
 }
 
+impl fmt::Display for PermissionsFormat {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(
+            fmt, "{}",
+            match self {
+                PermissionsFormat::Symbolic => "symbolic",
+                PermissionsFormat::Octal => "octal",
+            }
+        )
+    }
+}
+
 pub struct PrintDirConfig {
     pub max_row: usize,
     pub sort_by: ColumnKind,
     pub sort_reverse: bool,
     pub show_full_path: bool,
     pub show_hidden_files: bool,
+    pub show_disk_usage: bool,
+
+    // empty files and empty dirs have `⌀` after their names
+    pub mark_empty_entries: bool,
+
+    // it lists all the descendants in a flat list, instead of the children
+    // `recursive_depth: None` means unlimited
+    pub recursive: bool,
+    pub recursive_depth: Option<usize>,
+
+    // it only shows dirs, as deep as `max_row` allows (like `tree -d`)
+    // `;T` toggles it
+    pub tree_mode: bool,
+
+    // how deep `print_dir` shows the contents of the children, when there are enough rows
+    // 0 shows only the children, and levels deeper than 2 are not implemented yet
+    pub max_nested_level: usize,
+
+    // it shows the error log instead of the dir
+    pub show_error_log: bool,
+
+    // `;d` asks `delete <name>? [y/N]` before deleting a file
+    pub require_delete_confirmation: bool,
+
+    // `;w` opens the dir with this command, instead of the platform's default file manager
+    pub external_file_manager: Option<String>,
+
+    // it shows the number of entries read so far, while reading a slow dir
+    pub show_loading_indicator: bool,
+
+    // `home > projects > my_crate` above the table, `;b<N>` goes to the N-th component
+    pub show_breadcrumb: bool,
+
+    // `;v` adds a gray row below each file, with its mime type, inode and number of xattrs
+    // `max_row` is halved, because a file takes 2 rows
+    pub verbose: bool,
+
     pub max_width: usize,
     pub min_width: usize,
 
+    // spaces between columns, and between the borders and the columns
+    pub column_margin: usize,
+
+    // it only shows the files whose name matches the regex
+    pub filter: Option<Regex>,
+
+    // it only shows the files with these extensions
+    // `None` and an empty vector do nothing
+    pub ext_filter: Option<Vec<String>>,
+
+    // (dir, pattern) of `;G`
+    // when it's showing the dir, it lists the descendants whose contents match the pattern
+    pub grep: Option<(Uid, Regex)>,
+
     // every index is 0-based
     pub offset: usize,
 
     pub alert: String,
+
+    // `;t` toggles it, and `reset_alert` doesn't reset it
     pub show_elapsed_time: bool,
     pub elapsed_timer: Instant,
 
@@ -81,21 +251,51 @@ impl PrintDirConfig {
             let h = h as usize;
             self.max_width = w.max(36) - 4;
             self.min_width = self.max_width >> 2;
-            self.max_row = h.max(28).min(168) - 8;
+            self.max_row = h.max(28).min(168) - 8 - self.show_breadcrumb as usize;
+
+            if self.verbose {
+                self.max_row /= 2;
+            }
         }
     }
 
     pub fn reset_alert(&mut self) {
         self.alert = String::new();
-        self.show_elapsed_time = true;
         self.elapsed_timer = Instant::now();
     }
 
+    pub fn is_grep_dir(&self, uid: Uid) -> bool {
+        matches!(&self.grep, Some((dir, _)) if *dir == uid)
+    }
+
     pub fn into_sql_string(&self) -> String {
+        let mut conditions = vec![];
+
+        if !self.show_hidden_files {
+            conditions.push(String::from("is_hidden=false"));
+        }
+
+        if let Some(filter) = &self.filter {
+            conditions.push(format!("name REGEXP {}", sql_string_literal(filter.as_str())));
+        }
+
+        if let Some(exts) = &self.ext_filter {
+            if !exts.is_empty() {
+                conditions.push(format!(
+                    "extension IN ({})",
+                    exts.iter().map(|ext| sql_string_literal(ext)).collect::<Vec<_>>().join(", "),
+                ));
+            }
+        }
+
+        if let Some((_, pattern)) = &self.grep {
+            conditions.push(format!("content REGEXP {}", sql_string_literal(pattern.as_str())));
+        }
+
         format!(
             "SELECT {} FROM cwd{} ORDER BY {}{} LIMIT {}{};",
             self.columns[1..].iter().map(|col| col.col_name()).collect::<Vec<_>>().join(", "),
-            if !self.show_hidden_files { " WHERE is_hidden=false" } else { "" },
+            if !conditions.is_empty() { format!(" WHERE {}", conditions.join(" AND ")) } else { String::new() },
             self.sort_by.col_name(),
             if self.sort_reverse { " DESC" } else { "" },
             self.max_row,
@@ -104,6 +304,11 @@ impl PrintDirConfig {
     }
 }
 
+// it quotes `s` as a sql string literal, doubling the single quotes: `it's` -> `'it''s'`
+fn sql_string_literal(s: &str) -> String {
+    format!("'{}'", s.replace("'", "''"))
+}
+
 impl Default for PrintDirConfig {
     fn default() -> Self {
         PrintDirConfig {
@@ -112,8 +317,24 @@ impl Default for PrintDirConfig {
             sort_reverse: false,
             show_full_path: false,
             show_hidden_files: false,
+            show_disk_usage: true,
+            mark_empty_entries: true,
+            recursive: false,
+            recursive_depth: None,
+            tree_mode: false,
+            max_nested_level: 1,
+            show_error_log: false,
+            require_delete_confirmation: true,
+            external_file_manager: None,
+            show_loading_indicator: true,
+            show_breadcrumb: true,
+            verbose: false,
             max_width: 120,
             min_width: 64,
+            column_margin: COLUMN_MARGIN,
+            filter: None,
+            ext_filter: None,
+            grep: None,
             offset: 0,
             alert: String::new(),
             show_elapsed_time: true,
@@ -134,16 +355,88 @@ pub enum FileReadMode {
     Force(ViewerKind),
 }
 
+impl FileReadMode {
+    // Infer -> Text -> Hex -> Image -> Infer
+    pub fn next(&self) -> Self {
+        match self {
+            FileReadMode::Infer => FileReadMode::Force(ViewerKind::Text),
+            FileReadMode::Force(ViewerKind::Text) => FileReadMode::Force(ViewerKind::Hex),
+            FileReadMode::Force(ViewerKind::Hex) => FileReadMode::Force(ViewerKind::Image),
+            FileReadMode::Force(ViewerKind::Image) => FileReadMode::Infer,
+        }
+    }
+}
+
+impl fmt::Display for FileReadMode {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(
+            fmt, "{}",
+            match self {
+                FileReadMode::Infer => "auto",
+                FileReadMode::Force(ViewerKind::Text) => "text",
+                FileReadMode::Force(ViewerKind::Hex) => "hex",
+                FileReadMode::Force(ViewerKind::Image) => "image",
+            }
+        )
+    }
+}
+
 impl Default for FileReadMode {
     fn default() -> Self {
         FileReadMode::Infer
     }
 }
 
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum FileEncoding {
+    // utf-8, and then latin-1 or cp1252 if the content doesn't look binary
+    #[default]
+    Auto,
+    Utf8,
+    Latin1,
+    Cp1252,
+}
+
+impl FileEncoding {
+    // Auto -> Utf8 -> Latin1 -> Cp1252 -> Auto
+    pub fn next(&self) -> Self {
+        match self {
+            FileEncoding::Auto => FileEncoding::Utf8,
+            FileEncoding::Utf8 => FileEncoding::Latin1,
+            FileEncoding::Latin1 => FileEncoding::Cp1252,
+            FileEncoding::Cp1252 => FileEncoding::Auto,
+        }
+    }
+}
+
+impl fmt::Display for FileEncoding {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(
+            fmt, "{}",
+            match self {
+                FileEncoding::Auto => "auto",
+                FileEncoding::Utf8 => "utf-8",
+                FileEncoding::Latin1 => "latin-1",
+                FileEncoding::Cp1252 => "cp1252",
+            }
+        )
+    }
+}
+
+// themes of syntect that the file viewer can use, the first one is the default
+// `adjust_color_for_background` knows which of them are light themes
+pub const SYNTAX_THEMES: [&str; 4] = [
+    "base16-ocean.dark",
+    "Solarized (dark)",
+    "Solarized (light)",
+    "InspiredGitHub",
+];
+
 pub struct PrintFileConfig {
     pub max_row: usize,
     pub max_width: usize,
     pub min_width: usize,
+    pub column_margin: usize,
 
     // for text files, it's a line offset
     // for hex files, it's a byte offset
@@ -161,8 +454,36 @@ pub struct PrintFileConfig {
     // make sure that it's sorted
     pub highlights: Vec<usize>,
 
+    // the regex that the user searched
+    // the text viewer highlights the matched parts of the highlighted lines
+    pub search_pattern: Option<Regex>,
+
+    // `>>>` of the highlighted lines and the matched parts are in this color
+    pub highlight_color: Color,
+
+    // the highlighted lines have a dim version of `highlight_color` as their background
+    pub highlight_background: bool,
+
     pub read_mode: FileReadMode,
+    pub encoding: FileEncoding,
     pub syntax_highlight: Option<String>,  // name of extension
+
+    // one of `SYNTAX_THEMES`
+    pub syntax_theme: String,
+
+    // a tab is expanded to the next multiple of this
+    pub tab_width: usize,
+
+    // byte offset of the cursor of the hex viewer, `i` enters the cursor mode
+    // the byte is shown in inverted colors, and the alert shows its value
+    pub hex_cursor: Option<u64>,
+
+    // the text viewer wraps long lines instead of truncating them, `w` toggles it
+    pub wrap_lines: bool,
+
+    // `;W <N>` wraps lines at N columns, even if the terminal is wider
+    // `None` wraps at the width of the terminal
+    pub wrap_column: Option<usize>,
 }
 
 impl PrintFileConfig {
@@ -189,13 +510,23 @@ impl Default for PrintFileConfig {
             max_row: 60,
             max_width: 120,
             min_width: 64,
+            column_margin: COLUMN_MARGIN,
             offset: 0,
             alert: String::new(),
             show_elapsed_time: true,
             elapsed_timer: Instant::now(),
             highlights: vec![],
+            search_pattern: None,
+            highlight_color: colors::RED,
+            highlight_background: false,
             read_mode: FileReadMode::Infer,
+            encoding: FileEncoding::Auto,
             syntax_highlight: None,
+            syntax_theme: String::from(SYNTAX_THEMES[0]),
+            tab_width: 4,
+            hex_cursor: None,
+            wrap_lines: false,
+            wrap_column: None,
         }
     }
 }
@@ -204,9 +535,13 @@ pub struct PrintLinkConfig {
     pub max_row: usize,
     pub max_width: usize,
     pub min_width: usize,
+    pub column_margin: usize,
     pub alert: String,
     pub show_elapsed_time: bool,
     pub elapsed_timer: Instant,
+
+    // it shows the contents of the target below the link, if there are enough rows
+    pub show_target_preview: bool,
 }
 
 impl PrintLinkConfig {
@@ -233,9 +568,11 @@ impl Default for PrintLinkConfig {
             max_row: 60,
             max_width: 120,
             min_width: 64,
+            column_margin: COLUMN_MARGIN,
             alert: String::new(),
             show_elapsed_time: true,
             elapsed_timer: Instant::now(),
+            show_target_preview: true,
         }
     }
 }
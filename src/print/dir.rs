@@ -1,50 +1,80 @@
 use super::{
     calc_table_column_widths,
+    flip_buffer,
     print_error_message,
     print_horizontal_line,
     print_row,
     Alignment,
-    COLUMN_MARGIN,
     LineColor,
-    SCREEN_BUFFER,
 };
-use super::config::{ColumnKind, PrintDirConfig};
+use super::config::{ColumnKind, PermissionsFormat, PrintDirConfig};
 use super::result::PrintDirResult;
 use super::utils::{
+    categorize_age,
+    colorize_age,
+    colorize_depth,
+    colorize_disk_usage,
+    colorize_duration,
     colorize_name,
+    colorize_permission_char,
     colorize_size,
+    colorize_size_delta,
     colorize_time,
     colorize_type,
     format_duration,
+    format_element_range,
+    format_exact_size,
+    guess_mime_type,
+    get_relative_path,
+    prettify_checksum,
+    prettify_permissions,
     prettify_size,
+    prettify_size_delta,
     prettify_time,
 };
-use colored::Color;
+use colored::{Color, Colorize};
 use crate::colors;
+use crate::error::FileQueryError;
 use crate::file::File;
+use crate::search::get_match_count;
+use crate::session::record_size;
+use crate::tags::get_tag;
 use crate::uid::Uid;
+use crate::worker::{is_work_pending, send_work, WorkItem};
 use crate::utils::{
+    filetime_to_system_time,
+    get_breadcrumb,
+    get_disk_usage,
+    get_error_log,
     get_file_by_uid,
-    get_path_by_uid,
+    try_get_path_by_uid,
+    get_size_delta,
+    get_symlink_target,
+    get_xattr,
+    is_network_file_system,
     sort_files,
 };
-use std::collections::HashMap;
-use std::time::{Instant, SystemTime};
-
-macro_rules! print_to_buffer {
-    ($($arg:tt)*) => {
-        unsafe {
-            SCREEN_BUFFER.push(format!($($arg)*));
-        }
-    };
-}
-
-macro_rules! println_to_buffer {
-    ($($arg:tt)*) => {
-        print_to_buffer!($($arg)*);
-        print_to_buffer!("\n");
-    };
-}
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime};
+
+// see `PrintDirConfig::mark_empty_entries`
+const EMPTY_MARKER: char = '⌀';
+
+// `init_children_with_indicator` waits this long before showing the indicator
+const LOADING_INDICATOR_DELAY: Duration = Duration::from_millis(300);
+const LOADING_INDICATOR_INTERVAL: Duration = Duration::from_millis(100);
+
+// groups of hard links are colored with these, in order
+const HARD_LINK_PALETTE: [Color; 6] = [
+    Color::TrueColor { r: 64, g: 192, b: 192 },
+    Color::TrueColor { r: 192, g: 64, b: 192 },
+    Color::TrueColor { r: 192, g: 128, b: 32 },
+    Color::TrueColor { r: 96, g: 160, b: 255 },
+    Color::TrueColor { r: 160, g: 224, b: 64 },
+    Color::TrueColor { r: 255, g: 96, b: 128 },
+];
 
 /// It does NOT check whether the given `uid` is dir or not.
 /// It assumes that the given `uid` is valid.
@@ -52,21 +82,42 @@ pub fn print_dir(
     uid: Uid,
     config: &PrintDirConfig,
 ) -> PrintDirResult {
+    if config.show_error_log {
+        print_error_log(config);
+        return PrintDirResult::success();
+    }
+
+    debug_assert_uid_valid!(uid);
     let file = get_file_by_uid(uid).unwrap();
 
-    file.init_children();
+    if config.show_loading_indicator {
+        init_children_with_indicator(file);
+    }
+
+    else {
+        file.init_children();
+    }
 
-    let mut children_instances = file.get_children(config.show_hidden_files);
+    let mut children_instances = get_visible_children(file, config);
+
+    // `prettify_size` is not exact, so the alert shows the exact size of the file at the cursor
+    // an alert of a command comes first
+    let alert = match children_instances.get(config.offset) {
+        Some(child) if config.alert.is_empty() && config.columns.iter().any(|col| matches!(col, ColumnKind::Size)) => {
+            format!("size: {} bytes", format_exact_size(child.size))
+        },
+        _ => config.alert.clone(),
+    };
 
     // num of children BEFORE truncated
     let children_num = children_instances.len();
-    let curr_dir_path = match get_path_by_uid(uid) {
+    let curr_dir_path = match try_get_path_by_uid(uid) {
         Some(path) => path,
         None => {
             print_error_message(
                 Some(file),
                 None,
-                format!("get_path_by_uid({}) has failed", uid.debug_info()),
+                FileQueryError::PathNotFound(uid),
                 config.min_width,
                 config.max_width,
             );
@@ -74,8 +125,6 @@ pub fn print_dir(
         },
     };
 
-    sort_files(&mut children_instances, config.sort_by, config.sort_reverse);
-
     // it shows contents inside dirs (if there are enough rows)
     let mut nested_levels;
 
@@ -88,7 +137,17 @@ pub fn print_dir(
         nested_levels = vec![0; config.max_row];
     }
 
-    else if children_instances.len() + 4 < config.max_row {
+    else if config.tree_mode {
+        let (children_instances_, nested_levels_) = add_tree_contents(
+            children_instances,
+            config,
+        );
+        children_instances = children_instances_;
+        nested_levels = nested_levels_;
+    }
+
+    // a flat list doesn't need nested contents
+    else if children_instances.len() + 4 < config.max_row && !config.recursive && !config.is_grep_dir(uid) && config.max_nested_level > 0 {
         let (children_instances_, nested_levels_) = add_nested_contents(
             children_instances,
             &config,
@@ -132,22 +191,65 @@ pub fn print_dir(
     let mut column_alignments = vec![];
     let mut content_colors = vec![];
 
+    // the color tag is always the last column
+    let mut columns = config.columns.clone();
+
+    if let Some(index) = columns.iter().position(|col| matches!(col, ColumnKind::Color)) {
+        let tag_column = columns.remove(index);
+        columns.push(tag_column);
+    }
+
     // column names
-    table_contents.push(config.columns.iter().map(|col| col.header_string()).collect::<Vec<_>>());
+    table_contents.push(columns.iter().map(|col| col.header_string()).collect::<Vec<_>>());
     column_alignments.push(vec![Alignment::Center; table_contents[0].len()]);
     content_colors.push(vec![LineColor::All(colors::WHITE); table_contents[0].len()]);
 
+    // files that share the same checksum prefix are potential duplicates
+    let mut checksum_counts = HashMap::new();
+
+    if columns.iter().any(|col| matches!(col, ColumnKind::Checksum)) {
+        for child in children_instances.iter() {
+            if child.is_special_file() {
+                continue;
+            }
+
+            if let Some(checksum) = child.get_checksum() {
+                *checksum_counts.entry(prettify_checksum(&checksum)).or_insert(0) += 1;
+            }
+        }
+    }
+
+    // (device, inode) -> group index (1-based)
+    let mut hard_link_groups = HashMap::new();
+
+    if columns.iter().any(|col| matches!(col, ColumnKind::Hardness)) {
+        for child in children_instances.iter() {
+            // `nlink` of a dir counts `..` of its sub dirs
+            if !child.is_file() || child.nlink < 2 {
+                continue;
+            }
+
+            if let Some(inode) = child.inode {
+                let group_index = hard_link_groups.len() + 1;
+                hard_link_groups.entry(inode).or_insert(group_index);
+            }
+        }
+    }
+
     let mut table_index = config.offset;
     let mut table_sub_index = 0;
 
+    // index of the file that each row of `table_contents` belongs to (+1, because of the header)
+    // a file has 2 rows in the verbose mode
+    let mut row_indexes = vec![0];
+
     for (index, child) in children_instances.iter().enumerate() {
         let nested_level = nested_levels[index];
-        let has_to_use_half_arrow = nested_level > 0 && (index == nested_levels.len() - 1 || nested_levels[index + 1] < nested_level);
 
         if child.is_special_file() {
             let message = render_indented_message(
-                nested_level,
-                has_to_use_half_arrow,
+                &nested_levels,
+                index,
                 &child.name,
             );
             let col2_color = if nested_level > 0 {
@@ -171,10 +273,16 @@ pub fn print_dir(
                 LineColor::All(colors::WHITE),
                 col2_color,
             ]);
+            row_indexes.push(index + 1);
 
             continue;
         }
 
+        // `ColumnKind::Delta` of the next session compares with this
+        if let Some(path) = try_get_path_by_uid(child.uid) {
+            record_size(path, child.size);
+        }
+
         if nested_level == 0 {
             table_index += 1;
             table_sub_index = 0;
@@ -184,11 +292,10 @@ pub fn print_dir(
             table_sub_index += 1;
         }
 
-        else {
-            unreachable!();
-        }
-
-        let table_index_formatted = if table_sub_index == 0 {
+        // levels deeper than 1 don't have indexes
+        let table_index_formatted = if nested_level > 1 {
+            String::new()
+        } else if table_sub_index == 0 {
             format!("{}   ", table_index - 1)
         } else {
             format!(
@@ -200,12 +307,17 @@ pub fn print_dir(
 
         let name = if nested_level > 0 {  // nested contents do not show full path
             render_indented_message(
-                nested_level,
-                has_to_use_half_arrow,
+                &nested_levels,
+                index,
                 &child.name,
             )
         } else if config.show_full_path {
-            get_path_by_uid(child.uid).unwrap().to_string()
+            try_get_path_by_uid(child.uid).unwrap().to_string()
+        } else if config.recursive || config.is_grep_dir(uid) {  // relative to the current dir
+            match Path::new(try_get_path_by_uid(child.uid).unwrap()).strip_prefix(curr_dir_path) {
+                Ok(path) => path.to_string_lossy().to_string(),
+                Err(_) => child.name.clone(),
+            }
         } else {
             child.name.clone()
         };
@@ -214,26 +326,39 @@ pub fn print_dir(
         let mut curr_column_alignments = vec![];
         let mut curr_content_colors = vec![];
 
-        for column in config.columns.iter() {
+        for column in columns.iter() {
             match column {
                 ColumnKind::Index => {
                     curr_table_contents.push(table_index_formatted.clone());
                     curr_content_colors.push(LineColor::All(colors::WHITE));
                 },
                 ColumnKind::Name => {
-                    curr_table_contents.push(name.clone());
-                    let name_color = colorize_name(child.file_type, child.is_executable);
-
-                    if nested_level > 0 {
-                        curr_content_colors.push(color_arrows(
+                    let name_color = colorize_name(child.file_type, child.get_is_executable());
+                    let name_colors = if nested_level > 0 {
+                        color_arrows(
                             name_color,     // default color
                             colors::GREEN,  // arrow color
                             &name,
-                        ));
+                        )
+                    } else {
+                        LineColor::All(name_color)
+                    };
+
+                    if config.mark_empty_entries && child.is_empty() {
+                        let mut colors = match name_colors {
+                            LineColor::Each(colors) => colors,
+                            _ => vec![name_color; name.chars().count()],
+                        };
+                        colors.push(colors::WHITE);
+                        colors.push(colors::GRAY);
+
+                        curr_table_contents.push(format!("{name} {EMPTY_MARKER}"));
+                        curr_content_colors.push(LineColor::Each(colors));
                     }
 
                     else {
-                        curr_content_colors.push(LineColor::All(name_color));
+                        curr_table_contents.push(name.clone());
+                        curr_content_colors.push(name_colors);
                     }
                 },
                 ColumnKind::Size => {
@@ -241,13 +366,58 @@ pub fn print_dir(
                     curr_content_colors.push(LineColor::All(colorize_size(child.size)));
                 },
                 ColumnKind::TotalSize => {
-                    curr_table_contents.push(prettify_size(child.get_recursive_size()));
-                    curr_content_colors.push(LineColor::All(colorize_size(child.get_recursive_size())));
+                    let size = child.get_recursive_size();
+
+                    // the workers are computing it, and the screen is redrawn when they're done
+                    if is_work_pending(WorkItem::ComputeRecursiveSize(child.uid)) {
+                        curr_table_contents.push(String::from("..."));
+                        curr_content_colors.push(LineColor::All(colors::GRAY));
+                    }
+
+                    else {
+                        curr_table_contents.push(prettify_size(size));
+                        curr_content_colors.push(LineColor::All(colorize_size(size)));
+                    }
                 },
                 ColumnKind::Modified => {
                     curr_table_contents.push(prettify_time(&now, child.last_modified));
                     curr_content_colors.push(LineColor::All(colorize_time(&now, child.last_modified)));
                 },
+                ColumnKind::Birthtime => match child.created_at {
+                    Some(created_at) => {
+                        let created_at = filetime_to_system_time(created_at);
+
+                        curr_table_contents.push(prettify_time(&now, created_at));
+                        curr_content_colors.push(LineColor::All(colorize_time(&now, created_at)));
+                    },
+                    None => {
+                        curr_table_contents.push(String::from("n/a"));
+                        curr_content_colors.push(LineColor::All(colors::GRAY));
+                    },
+                },
+                ColumnKind::LastOpened => match child.last_opened {
+                    Some(last_opened) => {
+                        curr_table_contents.push(prettify_time(&now, last_opened));
+                        curr_content_colors.push(LineColor::All(colorize_time(&now, last_opened)));
+                    },
+                    // the workers will ask spotlight, and it'll be shown in the next render
+                    None if cfg!(target_os = "macos") => {
+                        send_work(WorkItem::ComputeLastOpened(child.uid));
+                        curr_table_contents.push(String::from("..."));
+                        curr_content_colors.push(LineColor::All(colors::GRAY));
+                    },
+                    None => {
+                        curr_table_contents.push(String::from("n/a"));
+                        curr_content_colors.push(LineColor::All(colors::GRAY));
+                    },
+                },
+                ColumnKind::Age => {
+                    // future timestamps are 'today'
+                    let secs = now.duration_since(child.last_modified).unwrap_or_default().as_secs();
+
+                    curr_table_contents.push(categorize_age(secs).to_string());
+                    curr_content_colors.push(LineColor::All(colorize_age(secs)));
+                },
                 ColumnKind::FileType => {
                     curr_table_contents.push(child.file_type.to_string());
                     curr_content_colors.push(LineColor::All(colorize_type(child.file_type)));
@@ -256,6 +426,168 @@ pub fn print_dir(
                     curr_table_contents.push(child.file_ext.clone().unwrap_or(String::new()));
                     curr_content_colors.push(LineColor::All(colors::WHITE));
                 },
+                ColumnKind::Checksum => match child.get_checksum() {
+                    Some(checksum) => {
+                        let checksum = prettify_checksum(&checksum);
+                        let is_duplicate = *checksum_counts.get(&checksum).unwrap_or(&0) > 1;
+
+                        curr_table_contents.push(checksum);
+                        curr_content_colors.push(LineColor::All(if is_duplicate { colors::YELLOW } else { colors::GRAY }));
+                    },
+                    // too big to hash
+                    None if child.is_file() => {
+                        curr_table_contents.push(String::from("..."));
+                        curr_content_colors.push(LineColor::All(colors::GRAY));
+                    },
+                    None => {
+                        curr_table_contents.push(String::new());
+                        curr_content_colors.push(LineColor::All(colors::GRAY));
+                    },
+                },
+                ColumnKind::RelativePath => {
+                    let path = get_relative_path(child.uid).unwrap_or_default();
+                    let name_color = colorize_name(child.file_type, child.get_is_executable());
+
+                    // only the last segment is colored
+                    let last_segment_len = match path.rsplit_once(std::path::MAIN_SEPARATOR) {
+                        Some((_, last_segment)) => last_segment.chars().count(),
+                        None => path.chars().count(),
+                    };
+                    let path_len = path.chars().count();
+
+                    curr_content_colors.push(LineColor::Each([
+                        vec![colors::GRAY; path_len - last_segment_len],
+                        vec![name_color; last_segment_len],
+                    ].concat()));
+                    curr_table_contents.push(path);
+                },
+                ColumnKind::SymlinkTarget => match get_symlink_target(child) {
+                    Some((target, exists)) => {
+                        curr_table_contents.push(target);
+                        curr_content_colors.push(LineColor::All(if exists { colors::YELLOW } else { colors::RED }));
+                    },
+                    None => {
+                        curr_table_contents.push(String::new());
+                        curr_content_colors.push(LineColor::All(colors::WHITE));
+                    },
+                },
+                ColumnKind::Preview => match child.get_preview() {
+                    Some(preview) => {
+                        curr_table_contents.push(preview);
+                        curr_content_colors.push(LineColor::All(colors::GRAY));
+                    },
+                    None => {
+                        curr_table_contents.push(String::new());
+                        curr_content_colors.push(LineColor::All(colors::GRAY));
+                    },
+                },
+                ColumnKind::WordCount => match child.wc {
+                    Some(Some((lines, words, _))) => {
+                        curr_table_contents.push(format!("{lines}L {words}W"));
+                        curr_content_colors.push(LineColor::All(colors::WHITE));
+                    },
+                    // the workers will count it, and it'll be shown in the next render
+                    None if child.is_file() => {
+                        send_work(WorkItem::ComputeWordCount(child.uid));
+                        curr_table_contents.push(String::from("..."));
+                        curr_content_colors.push(LineColor::All(colors::GRAY));
+                    },
+                    _ => {
+                        curr_table_contents.push(String::from("-"));
+                        curr_content_colors.push(LineColor::All(colors::GRAY));
+                    },
+                },
+                ColumnKind::EntryCount => if child.is_dir() {
+                    let children = child.get_children(config.show_hidden_files);
+                    let count = if config.tree_mode {
+                        children.iter().filter(|c| !c.is_dir()).count()
+                    } else {
+                        children.len()
+                    };
+
+                    curr_table_contents.push(count.to_string());
+                    curr_content_colors.push(LineColor::All(colors::WHITE));
+                } else {
+                    curr_table_contents.push(String::from("-"));
+                    curr_content_colors.push(LineColor::All(colors::GRAY));
+                },
+                ColumnKind::Permissions(format) => match child.mode {
+                    Some(mode) => {
+                        let permissions = prettify_permissions(mode, *format);
+                        let permissions_colors = match format {
+                            PermissionsFormat::Symbolic => LineColor::Each(permissions.chars().map(colorize_permission_char).collect()),
+                            PermissionsFormat::Octal => LineColor::All(colors::WHITE),
+                        };
+
+                        curr_table_contents.push(permissions);
+                        curr_content_colors.push(permissions_colors);
+                    },
+                    None => {
+                        curr_table_contents.push(String::from("-"));
+                        curr_content_colors.push(LineColor::All(colors::GRAY));
+                    },
+                },
+                ColumnKind::NetworkMount => {
+                    curr_table_contents.push(if child.is_network_mount { String::from("net") } else { String::new() });
+                    curr_content_colors.push(LineColor::All(colors::BLUE));
+                },
+                ColumnKind::CustomTag(key) => match try_get_path_by_uid(child.uid).map(|path| get_xattr(path, key)) {
+                    Some(Ok(Some(value))) => {
+                        curr_table_contents.push(value);
+                        curr_content_colors.push(LineColor::All(colors::WHITE));
+                    },
+                    Some(Ok(None)) => {
+                        curr_table_contents.push(String::from("-"));
+                        curr_content_colors.push(LineColor::All(colors::GRAY));
+                    },
+                    // the file system doesn't support xattrs
+                    _ => {
+                        curr_table_contents.push(String::from("n/a"));
+                        curr_content_colors.push(LineColor::All(colors::GRAY));
+                    },
+                },
+                ColumnKind::Color => match try_get_path_by_uid(child.uid).and_then(get_tag) {
+                    Some(tag) => {
+                        curr_table_contents.push(String::from("●"));
+                        curr_content_colors.push(LineColor::All(tag.to_color()));
+                    },
+                    None => {
+                        curr_table_contents.push(String::new());
+                        curr_content_colors.push(LineColor::All(colors::WHITE));
+                    },
+                },
+                ColumnKind::MatchCount => match get_match_count(child.uid) {
+                    Some(count) => {
+                        curr_table_contents.push(count.to_string());
+                        curr_content_colors.push(LineColor::All(colors::YELLOW));
+                    },
+                    None => {
+                        curr_table_contents.push(String::from("-"));
+                        curr_content_colors.push(LineColor::All(colors::GRAY));
+                    },
+                },
+                ColumnKind::Delta => {
+                    let delta = get_size_delta(child);
+
+                    curr_table_contents.push(prettify_size_delta(delta));
+                    curr_content_colors.push(LineColor::All(colorize_size_delta(delta)));
+                },
+                ColumnKind::Hardness => match child.inode.and_then(|inode| hard_link_groups.get(&inode)) {
+                    Some(group_index) if child.nlink > 1 => {
+                        curr_table_contents.push(format!("[H{group_index}]"));
+                        curr_content_colors.push(LineColor::All(HARD_LINK_PALETTE[(group_index - 1) % HARD_LINK_PALETTE.len()]));
+                    },
+                    _ => {
+                        curr_table_contents.push(String::new());
+                        curr_content_colors.push(LineColor::All(colors::WHITE));
+                    },
+                },
+                ColumnKind::Depth => {
+                    let depth = child.get_depth();
+
+                    curr_table_contents.push(depth.to_string());
+                    curr_content_colors.push(LineColor::All(colorize_depth(depth)));
+                },
             }
 
             curr_column_alignments.push(column.alignment());
@@ -264,37 +596,73 @@ pub fn print_dir(
         table_contents.push(curr_table_contents);
         column_alignments.push(curr_column_alignments);
         content_colors.push(curr_content_colors);
+        row_indexes.push(index + 1);
+
+        // it spans every column after the index
+        if config.verbose {
+            table_contents.push(vec![
+                String::new(),  // index
+                render_verbose_info(child),
+            ]);
+            column_alignments.push(vec![
+                Alignment::Right,
+                Alignment::Left,
+            ]);
+            content_colors.push(vec![
+                LineColor::All(colors::GRAY),
+                LineColor::All(colors::GRAY),
+            ]);
+            row_indexes.push(index + 1);
+        }
     }
 
     let table_column_widths = calc_table_column_widths(
         &table_contents,
         Some(config.max_width),
         Some(config.min_width),
-        COLUMN_MARGIN,
+        config.column_margin,
     );
     let curr_table_width = {
         let (cols, widths) = table_column_widths.iter().next().unwrap();
 
-        widths.iter().sum::<usize>() + COLUMN_MARGIN * (*cols + 1)
+        widths.iter().sum::<usize>() + config.column_margin * (*cols + 1)
     };
 
+    if config.show_breadcrumb {
+        let (breadcrumb, breadcrumb_colors) = render_breadcrumb(uid, config.max_width);
+
+        print_row(
+            colors::BLACK,
+            &vec![breadcrumb.clone()],
+            &vec![breadcrumb.chars().count()],
+            &vec![Alignment::Left],
+            &vec![LineColor::Each(breadcrumb_colors)],
+            0,
+            (false, false),
+        );
+    }
+
     print_horizontal_line(
         None,  // background
         curr_table_width,
         (true, false),   // (is top, is bottom)
         (true, true),    // (left border, right border)
+        None,            // title
     );
 
+    let element_range = format_element_range(config.offset, shown_rows, children_num);
+    let element_range_width = element_range.chars().count().max(13);
+
     // print curr dir
     print_row(
         colors::BLACK,
         &vec![
             curr_dir_path.to_string(),
-            format!("{} elements", children_num),
+            element_range,
         ],
         &vec![
-            curr_table_width - 13 - COLUMN_MARGIN * 3,
-            13,
+            curr_table_width.saturating_sub(element_range_width + config.column_margin * 3),
+            element_range_width,
         ],
         &vec![
             Alignment::Left,    // path
@@ -304,19 +672,35 @@ pub fn print_dir(
             LineColor::All(colors::WHITE),  // path
             LineColor::All(colors::YELLOW),  // num of elements
         ],
-        COLUMN_MARGIN,
+        config.column_margin,
         (true, true),
     );
 
+    if config.show_disk_usage {
+        if let Some((total, available)) = get_disk_usage(curr_dir_path) {
+            print_disk_usage(total, available, curr_table_width.saturating_sub(config.column_margin * 2), config.column_margin);
+        }
+    }
+
     print_horizontal_line(
         None,  // background
         curr_table_width,
         (false, false),  // (is top, is bottom)
         (true, true),    // (left border, right border)
+        None,            // title
     );
 
     for index in 0..table_contents.len() {
-        let background = if index & 1 == 1 { colors::DARK_GRAY } else { colors::BLACK };
+        let row_index = row_indexes[index];
+
+        // `table_contents[0]` is the header
+        let background = if row_index > 0 && nested_levels[row_index - 1] > 0 {
+            colors::secondary_background()
+        } else if row_index & 1 == 1 {
+            colors::DARK_GRAY
+        } else {
+            colors::BLACK
+        };
         let column_widths = table_column_widths.get(&table_contents[index].len()).unwrap();
 
         print_row(
@@ -325,7 +709,7 @@ pub fn print_dir(
             column_widths,
             &column_alignments[index],
             &content_colors[index],
-            COLUMN_MARGIN,
+            config.column_margin,
             (true, true),
         );
     }
@@ -335,17 +719,275 @@ pub fn print_dir(
         curr_table_width,
         (false, true),   // (is top, is bottom)
         (true, true),    // (left border, right border)
+        None,            // title
+    );
+
+    // the elapsed time is in the same line as the query
+    let elapsed_time = if config.show_elapsed_time {
+        let elapsed_time = Instant::now().duration_since(config.elapsed_timer);
+
+        format!(
+            "  {}",
+            format!("took {}", format_duration(elapsed_time)).color(colorize_duration(elapsed_time)),
+        )
+    } else {
+        String::new()
+    };
+
+    println_to_buffer!("{}{elapsed_time}", config.into_sql_string());
+    println_to_buffer!("{alert}");
+
+    PrintDirResult::success()
+}
+
+/// Children of `file` in the order that `print_dir` shows them.
+/// They're filtered and sorted, but not truncated.
+pub fn get_visible_children<'a>(
+    file: &'a File,
+    config: &PrintDirConfig,
+) -> Vec<&'a File> {
+    let mut children = if config.is_grep_dir(file.uid) {
+        let mut descendants = file.get_descendants(config.show_hidden_files, None);
+        descendants.retain(|descendant| get_match_count(descendant.uid).is_some());
+        descendants
+    } else if config.recursive {
+        file.get_descendants(config.show_hidden_files, config.recursive_depth)
+    } else {
+        file.get_children(config.show_hidden_files)
+    };
+
+    if config.tree_mode {
+        children.retain(|child| child.is_dir());
+    }
+
+    if let Some(filter) = &config.filter {
+        children.retain(|child| filter.is_match(&child.name));
+    }
+
+    if let Some(exts) = &config.ext_filter {
+        if !exts.is_empty() {
+            children.retain(
+                |child| match &child.file_ext {
+                    Some(ext) => exts.contains(ext),
+                    None => false,
+                }
+            );
+        }
+    }
+
+    // in a flat list, names are paths
+    if (config.recursive || config.is_grep_dir(file.uid)) && matches!(config.sort_by, ColumnKind::Name) {
+        sort_files(&mut children, ColumnKind::RelativePath, config.sort_reverse);
+    }
+
+    else {
+        sort_files(&mut children, config.sort_by, config.sort_reverse);
+    }
+
+    children
+}
+
+// the last `max_row` errors of `File::from_io_error` and `File::from_error_msg`
+fn print_error_log(config: &PrintDirConfig) {
+    let error_log = get_error_log();
+    let mut rows = vec![];
+
+    for (path, message) in error_log[(error_log.len().max(config.max_row) - config.max_row)..].iter() {
+        rows.push(vec![
+            path.to_string(),
+            String::from("│"),
+            message.to_string(),
+        ]);
+    }
+
+    if rows.is_empty() {
+        rows.push(vec![String::from("no errors")]);
+    }
+
+    let column_widths = calc_table_column_widths(
+        &rows,
+        Some(config.max_width),
+        Some(config.min_width),
+        config.column_margin,
+    );
+    let table_width = column_widths.values().next().unwrap().iter().sum::<usize>() + config.column_margin * (rows[0].len() - 1);
+
+    print_horizontal_line(
+        None,
+        table_width + config.column_margin * 2,
+        (true, false),
+        (true, true),
+        None,
+    );
+    print_row(
+        colors::BLACK,
+        &vec![format!("error log ({} errors)", error_log.len())],
+        &vec![table_width],
+        &vec![Alignment::Center],
+        &vec![LineColor::All(colors::WHITE)],
+        config.column_margin,
+        (true, true),
+    );
+    print_horizontal_line(
+        None,
+        table_width + config.column_margin * 2,
+        (false, false),
+        (true, true),
+        None,
+    );
+
+    for row in rows.iter() {
+        print_row(
+            colors::BLACK,
+            row,
+            column_widths.get(&row.len()).unwrap(),
+            &vec![Alignment::Left; row.len()],
+            &if row.len() == 1 {
+                vec![LineColor::All(colors::WHITE)]
+            } else {
+                vec![
+                    LineColor::All(colors::WHITE),  // path
+                    LineColor::All(colors::WHITE),  // border
+                    LineColor::All(colors::RED),    // message
+                ]
+            },
+            config.column_margin,
+            (true, true),
+        );
+    }
+
+    print_horizontal_line(
+        None,
+        table_width + config.column_margin * 2,
+        (false, true),
+        (true, true),
+        None,
     );
-    println_to_buffer!("{}", config.into_sql_string());
 
     println_to_buffer!(
         "{}{}{}",
         config.alert,
         if !config.alert.is_empty() && config.show_elapsed_time { ": " } else { "" },
-        if config.show_elapsed_time { format!("took {}", format_duration(Instant::now().duration_since(config.elapsed_timer.clone()))) } else { String::new() },
+        if config.show_elapsed_time { format!("took {}", format_duration(Instant::now().duration_since(config.elapsed_timer))) } else { String::new() },
     );
+}
 
-    PrintDirResult::success()
+// the second row of a file in the verbose mode
+fn render_verbose_info(file: &File) -> String {
+    #[cfg(unix)]
+    let xattr_count = try_get_path_by_uid(file.uid).and_then(|path| xattr::list(path).ok()).map(|keys| keys.count());
+
+    #[cfg(not(unix))]
+    let xattr_count: Option<usize> = None;
+
+    format!(
+        "mime: {}  inode: {}  xattrs: {}",
+        guess_mime_type(file.file_type, file.file_ext.as_deref()),
+        file.inode.map(|(_, inode)| inode.to_string()).unwrap_or(String::from("n/a")),
+        xattr_count.map(|count| count.to_string()).unwrap_or(String::from("n/a")),
+    )
+}
+
+// `home > projects > my_crate`, the current dir is green
+// if it's wider than `max_width`, the leftmost components are replaced with `...`
+fn render_breadcrumb(uid: Uid, max_width: usize) -> (String, Vec<Color>) {
+    let mut names = get_breadcrumb(uid).into_iter().map(
+        |uid| match get_file_by_uid(uid) {
+            Some(file) if file.name.is_empty() => String::from(std::path::MAIN_SEPARATOR),
+            Some(file) => file.name.clone(),
+            None => String::from("?"),
+        }
+    ).collect::<Vec<_>>();
+    let mut is_truncated = false;
+
+    while names.len() > 1 && names.iter().map(|name| name.chars().count() + 3).sum::<usize>() + if is_truncated { 4 } else { 0 } > max_width {
+        names.remove(0);
+        is_truncated = true;
+    }
+
+    let curr_name = names.pop().unwrap_or_default();
+    let mut result = if is_truncated { String::from("... > ") } else { String::new() };
+
+    for name in names.iter() {
+        result = format!("{result}{name} > ");
+    }
+
+    let mut result_colors = vec![colors::GRAY; result.chars().count()];
+    result_colors.extend(vec![colors::GREEN; curr_name.chars().count()]);
+
+    (format!("{result}{curr_name}"), result_colors)
+}
+
+// it doesn't show anything if the dir is read within `LOADING_INDICATOR_DELAY`
+// network file systems show the indicator immediately
+fn init_children_with_indicator(file: &mut File) {
+    if file.children.is_some() || !file.is_dir() {
+        return;
+    }
+
+    let started_at = Instant::now();
+    let mut last_update = None;
+    let is_network = try_get_path_by_uid(file.uid).map(is_network_file_system).unwrap_or(false);
+
+    file.init_children_with_progress(&mut |entries| {
+        let now = Instant::now();
+
+        if !is_network && now.duration_since(started_at) < LOADING_INDICATOR_DELAY {
+            return;
+        }
+
+        if let Some(last_update) = last_update {
+            if now.duration_since(last_update) < LOADING_INDICATOR_INTERVAL {
+                return;
+            }
+        }
+
+        last_update = Some(now);
+        print_loading_indicator(entries);
+    });
+
+    // the table starts from a new line
+    if last_update.is_some() {
+        print_to_buffer!("\n");
+    }
+}
+
+// it's flushed immediately, unlike the other contents of the buffer
+fn print_loading_indicator(entries: usize) {
+    print_to_buffer!("\r{}", format!("Loading... ({entries} entries found so far)").color(colors::GRAY));
+    flip_buffer(false);
+    let _ = io::stdout().flush();
+}
+
+// [███████░░░░] 42% used  58 GiB free
+fn print_disk_usage(total: u64, available: u64, width: usize, margin: usize) {
+    let used_ratio = if total == 0 { 0.0 } else { 1.0 - available as f64 / total as f64 };
+    let description = format!(
+        "{}% used  {} free",
+        (used_ratio * 100.0).round() as usize,
+        prettify_size(available).trim(),
+    );
+    let bar_width = width.max(description.len() + 12) - description.len() - 4;
+    let filled = ((bar_width as f64 * used_ratio).round() as usize).min(bar_width);
+
+    print_row(
+        colors::BLACK,
+        &vec![format!(
+            "[{}{}]  {description}",
+            "█".repeat(filled),
+            "░".repeat(bar_width - filled),
+        )],
+        &vec![width],
+        &vec![Alignment::Left],
+        &vec![LineColor::Each([
+            vec![colors::WHITE],
+            vec![colorize_disk_usage(used_ratio); filled],
+            vec![colors::GRAY; bar_width - filled],
+            vec![colors::WHITE; description.len() + 3],
+        ].concat())],
+        margin,
+        (true, true),
+    );
 }
 
 // it doesn't check whether `content` has arrows or not
@@ -364,7 +1006,7 @@ fn color_arrows(
         }
 
         else {
-            if c == '├' || c == '─' || c == '╰' || c == ' ' {
+            if c == '├' || c == '─' || c == '╰' || c == '│' || c == ' ' {
                 result.push(arrow_color);
             }
 
@@ -378,53 +1020,150 @@ fn color_arrows(
     LineColor::Each(result)
 }
 
+// `nested_levels[index]` is the indent level of the message
 fn render_indented_message(
-    indent_level: usize,
-    use_half_arrow: bool,
+    nested_levels: &[usize],
+    index: usize,
     message: &str,
 ) -> String {
-    match indent_level {
-        0 => message.to_string(),
-        1 if use_half_arrow => format!("╰── {message}"),
-        1 => format!("├── {message}"),
-        _ => unreachable!(),
+    let indent_level = nested_levels[index];
+
+    if indent_level == 0 {
+        return message.to_string();
     }
+
+    let mut result = vec![];
+
+    // vertical lines of the ancestors
+    for level in 1..indent_level {
+        if has_sibling_after(nested_levels, index, level) {
+            result.push("│   ");
+        }
+
+        else {
+            result.push("    ");
+        }
+    }
+
+    if has_sibling_after(nested_levels, index, indent_level) {
+        result.push("├── ");
+    }
+
+    else {
+        result.push("╰── ");
+    }
+
+    result.push(message);
+    result.concat()
+}
+
+// whether there's another row at `level` after `index`, under the same parent
+fn has_sibling_after(nested_levels: &[usize], index: usize, level: usize) -> bool {
+    for nested_level in nested_levels[(index + 1)..].iter() {
+        if *nested_level < level {
+            return false;
+        }
+
+        else if *nested_level == level {
+            return true;
+        }
+    }
+
+    false
 }
 
 fn add_nested_contents<'a>(
     contents: Vec<&'a File>,
     config: &PrintDirConfig,
 ) -> (Vec<&'a File>, Vec<usize>) {
-    let mut number_of_children_to_show = HashMap::new();
     let mut remaining_rows = config.max_row - contents.len();
 
+    // `number_of_children_to_show[level - 1]` is for the dirs at `level - 1`
+    let mut number_of_children_to_show = vec![
+        distribute_rows(&contents, &mut remaining_rows, config),
+    ];
+    let mut parents = contents.clone();
+
+    // it goes deeper only when all the contents of the previous level fit in the rows
+    while number_of_children_to_show.len() < config.max_nested_level && remaining_rows >= 4 {
+        let mut children = vec![];
+
+        for parent in parents.iter() {
+            if *number_of_children_to_show.last().unwrap().get(&parent.uid).unwrap() > 0 {
+                children.extend(parent.get_children_sorted(config.show_hidden_files, config.sort_by, config.sort_reverse));
+            }
+        }
+
+        // reserves a row for the truncation message of each non-empty dir
+        let non_empty_dirs = children.iter().filter(|child| child.get_children_num(config.show_hidden_files) > 0).count();
+        remaining_rows = remaining_rows.max(non_empty_dirs) - non_empty_dirs;
+        number_of_children_to_show.push(distribute_rows(&children, &mut remaining_rows, config));
+        parents = children;
+    }
+
+    let mut new_contents = vec![];
+    let mut nested_levels = vec![];
+
     for content in contents.iter() {
-        let children_num = content.get_children_num(config.show_hidden_files);
+        new_contents.push(content.uid);
+        nested_levels.push(0);
+        push_nested_contents(
+            content,
+            1,
+            &number_of_children_to_show,
+            config,
+            &mut new_contents,
+            &mut nested_levels,
+        );
+    }
 
-        if children_num > 0 && remaining_rows > 0 {
-            number_of_children_to_show.insert(content.uid, 1);
-            remaining_rows -= 1;
+    (
+        new_contents.iter().map(
+            |uid| {
+                debug_assert_uid_valid!(*uid);
+                get_file_by_uid(*uid).unwrap() as &File
+            }
+        ).collect(),
+        nested_levels,
+    )
+}
+
+// It decides how many children of each dir in `parents` to show.
+// Every dir gets at least 1 row (if there are enough), and the remaining rows are distributed evenly.
+fn distribute_rows(
+    parents: &[&File],
+    remaining_rows: &mut usize,
+    config: &PrintDirConfig,
+) -> HashMap<Uid, usize> {
+    let mut number_of_children_to_show = HashMap::new();
+
+    for parent in parents.iter() {
+        let children_num = parent.get_children_num(config.show_hidden_files);
+
+        if children_num > 0 && *remaining_rows > 0 {
+            number_of_children_to_show.insert(parent.uid, 1);
+            *remaining_rows -= 1;
         }
 
         else {
-            number_of_children_to_show.insert(content.uid, 0);
+            number_of_children_to_show.insert(parent.uid, 0);
         }
     }
 
     loop {
-        if remaining_rows < 4 {
+        if *remaining_rows < 4 {
             break;
         }
 
         let mut added_something = false;
 
-        for content in contents.iter() {
-            let children_num = content.get_children_num(config.show_hidden_files);
-            let children_to_show = number_of_children_to_show.get_mut(&content.uid).unwrap();
+        for parent in parents.iter() {
+            let children_num = parent.get_children_num(config.show_hidden_files);
+            let children_to_show = number_of_children_to_show.get_mut(&parent.uid).unwrap();
 
-            if remaining_rows > 0 && *children_to_show < children_num {
+            if *remaining_rows > 0 && *children_to_show < children_num {
                 *children_to_show += 1;
-                remaining_rows -= 1;
+                *remaining_rows -= 1;
                 added_something = true;
             }
         }
@@ -434,36 +1173,135 @@ fn add_nested_contents<'a>(
         }
     }
 
-    // TODO: if there're still remaining rows, show level-2 contents
+    number_of_children_to_show
+}
 
-    let mut new_contents = vec![];
-    let mut nested_levels = vec![];
+fn push_nested_contents(
+    dir: &File,
+    nested_level: usize,
+    number_of_children_to_show: &[HashMap<Uid, usize>],
+    config: &PrintDirConfig,
+    new_contents: &mut Vec<Uid>,
+    nested_levels: &mut Vec<usize>,
+) {
+    let children_to_show = match number_of_children_to_show.get(nested_level - 1) {
+        Some(rows) => *rows.get(&dir.uid).unwrap_or(&0),
+        None => 0,
+    };
 
-    for content in contents.iter() {
-        new_contents.push(content.uid);
-        nested_levels.push(0);
-        let children_to_show = *number_of_children_to_show.get(&content.uid).unwrap();
+    if children_to_show == 0 {
+        return;
+    }
 
-        if children_to_show > 0 {
-            let mut children = content.get_children(config.show_hidden_files);
-            sort_files(&mut children, config.sort_by, config.sort_reverse);
+    let children = dir.get_children_sorted(config.show_hidden_files, config.sort_by, config.sort_reverse);
+
+    for child in children[..children_to_show].iter() {
+        new_contents.push(child.uid);
+        nested_levels.push(nested_level);
+        push_nested_contents(
+            child,
+            nested_level + 1,
+            number_of_children_to_show,
+            config,
+            new_contents,
+            nested_levels,
+        );
+    }
 
-            for child in children[..children_to_show].iter() {
-                new_contents.push(child.uid);
-                nested_levels.push(1);
-            }
+    if children.len() > children_to_show {
+        new_contents.push(File::message_for_truncated_rows(children.len() - children_to_show));
+        nested_levels.push(nested_level);
+    }
+}
+
+// It's like `add_nested_contents`, but
+// 1. it only shows dirs
+// 2. it goes as deep as the rows allow (level by level)
+fn add_tree_contents<'a>(
+    contents: Vec<&'a File>,
+    config: &PrintDirConfig,
+) -> (Vec<&'a File>, Vec<usize>) {
+    let mut expanded_dirs = HashSet::new();
+    let mut remaining_rows = config.max_row.max(contents.len()) - contents.len();
+    let mut frontier = contents.iter().map(|content| content.uid).collect::<Vec<_>>();
+
+    while !frontier.is_empty() && remaining_rows > 0 {
+        let mut next_frontier = vec![];
 
-            if children.len() > children_to_show {
-                new_contents.push(File::message_for_truncated_rows(children.len() - children_to_show));
-                nested_levels.push(1);
+        for dir in frontier.iter() {
+            let children = get_child_dirs(*dir, config);
+
+            if children.len() > remaining_rows {
+                break;
             }
+
+            remaining_rows -= children.len();
+            expanded_dirs.insert(*dir);
+            next_frontier.extend(children);
         }
+
+        // it couldn't expand all the dirs in this level
+        if frontier.iter().any(|dir| !expanded_dirs.contains(dir)) {
+            break;
+        }
+
+        frontier = next_frontier;
+    }
+
+    let mut new_contents = vec![];
+    let mut nested_levels = vec![];
+
+    for content in contents.iter() {
+        push_tree_contents(
+            content.uid,
+            0,
+            &expanded_dirs,
+            config,
+            &mut new_contents,
+            &mut nested_levels,
+        );
     }
 
     (
         new_contents.iter().map(
-            |uid| get_file_by_uid(*uid).unwrap() as &File
+            |uid| {
+                debug_assert_uid_valid!(*uid);
+                get_file_by_uid(*uid).unwrap() as &File
+            }
         ).collect(),
         nested_levels,
     )
 }
+
+fn push_tree_contents(
+    dir: Uid,
+    nested_level: usize,
+    expanded_dirs: &HashSet<Uid>,
+    config: &PrintDirConfig,
+    new_contents: &mut Vec<Uid>,
+    nested_levels: &mut Vec<usize>,
+) {
+    new_contents.push(dir);
+    nested_levels.push(nested_level);
+
+    if expanded_dirs.contains(&dir) {
+        for child in get_child_dirs(dir, config) {
+            push_tree_contents(
+                child,
+                nested_level + 1,
+                expanded_dirs,
+                config,
+                new_contents,
+                nested_levels,
+            );
+        }
+    }
+}
+
+fn get_child_dirs(dir: Uid, config: &PrintDirConfig) -> Vec<Uid> {
+    debug_assert_uid_valid!(dir);
+    let mut children = get_file_by_uid(dir).unwrap().get_children_sorted(config.show_hidden_files, config.sort_by, config.sort_reverse);
+    children.retain(|child| child.is_dir());
+
+    children.iter().map(|child| child.uid).collect()
+}
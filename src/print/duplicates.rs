@@ -0,0 +1,124 @@
+use super::{
+    calc_table_column_widths,
+    print_horizontal_line,
+    print_row,
+    Alignment,
+    LineColor,
+};
+use super::config::PrintDirConfig;
+use super::utils::{prettify_checksum, prettify_size};
+use crate::colors;
+use crate::uid::Uid;
+use crate::utils::try_get_path_by_uid;
+use std::path::Path;
+
+// (size, checksum, files with the same contents), see `;D`
+pub type DuplicateGroup = (u64, [u8; 32], Vec<Uid>);
+
+// one section per group, and the paths are relative to `base`
+// it shows as many groups as `config.max_row` allows
+pub fn print_duplicates(base: Uid, groups: &[DuplicateGroup], config: &PrintDirConfig) {
+    let base_path = try_get_path_by_uid(base).unwrap_or("");
+    let mut rows = vec![vec![format!("{} groups of duplicates", groups.len())]];
+    let mut is_group_header = vec![false];
+    let mut shown_groups = 0;
+
+    for (size, checksum, uids) in groups.iter() {
+        if shown_groups > 0 && rows.len() + uids.len() + 1 > config.max_row {
+            break;
+        }
+
+        rows.push(vec![format!(
+            "sha256 {}, {} x {}",
+            prettify_checksum(checksum),
+            prettify_size(*size).trim(),
+            uids.len(),
+        )]);
+        is_group_header.push(true);
+
+        for uid in uids.iter() {
+            let path = try_get_path_by_uid(*uid).unwrap_or("");
+            let path = match Path::new(path).strip_prefix(base_path) {
+                Ok(relative_path) => relative_path.to_string_lossy().to_string(),
+                Err(_) => path.to_string(),
+            };
+
+            rows.push(vec![path]);
+            is_group_header.push(false);
+        }
+
+        shown_groups += 1;
+    }
+
+    let column_widths = calc_table_column_widths(
+        &rows,
+        Some(config.max_width),
+        None,
+        config.column_margin,
+    );
+    let column_widths = column_widths.get(&1).unwrap();
+    let table_width = column_widths[0] + config.column_margin * 2;
+
+    print_horizontal_line(
+        None,
+        table_width,
+        (true, false),
+        (true, true),
+        Some("duplicates"),
+    );
+    print_row(
+        colors::BLACK,
+        &rows[0],
+        column_widths,
+        &vec![Alignment::Center],
+        &vec![LineColor::All(colors::WHITE)],
+        config.column_margin,
+        (true, true),
+    );
+
+    for (row, is_group_header) in rows[1..].iter().zip(is_group_header[1..].iter()) {
+        if *is_group_header {
+            print_horizontal_line(
+                None,
+                table_width,
+                (false, false),
+                (true, true),
+                None,
+            );
+        }
+
+        print_row(
+            colors::BLACK,
+            row,
+            column_widths,
+            &vec![Alignment::Left],
+            &vec![LineColor::All(if *is_group_header { colors::YELLOW } else { colors::WHITE })],
+            config.column_margin,
+            (true, true),
+        );
+    }
+
+    print_horizontal_line(
+        None,
+        table_width,
+        (false, true),
+        (true, true),
+        None,
+    );
+
+    let hidden_groups = groups.len() - shown_groups;
+
+    print_row(
+        colors::BLACK,
+        &vec![if hidden_groups > 0 {
+            format!("and {hidden_groups} more groups, press any key to close")
+        } else {
+            String::from("press any key to close")
+        }],
+        &vec![table_width],
+        &vec![Alignment::Left],
+        &vec![LineColor::All(colors::GRAY)],
+        0,
+        (false, false),
+    );
+}
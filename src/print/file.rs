@@ -4,26 +4,28 @@ use super::{
     print_horizontal_line,
     print_row,
     Alignment,
-    COLUMN_MARGIN,
     LineColor,
-    SCREEN_BUFFER,
 };
-use super::config::PrintFileConfig;
-use super::result::PrintFileResult;
+use super::config::{FileEncoding, FileReadMode, PrintFileConfig, SYNTAX_THEMES};
+use super::result::{PrintFileResult, ViewerKind};
 use super::utils::{
-    convert_ocean_dark_color,
+    colorize_byte,
+    adjust_color_for_background,
+    decode_text,
     format_duration,
     prettify_size,
-    try_extract_utf8_text,
     try_read_image,
 };
+use crate::archive::read_archive_member;
 use crate::colors;
+use crate::error::FileQueryError;
 use crate::uid::Uid;
 use crate::utils::{
-    get_path_by_uid,
+    try_get_path_by_uid,
     get_file_by_uid,
 };
 use lazy_static::lazy_static;
+use std::collections::HashSet;
 use std::fs;
 use std::io::Read;
 use std::time::Instant;
@@ -38,82 +40,107 @@ use std::os::unix::fs::FileExt;
 #[cfg(not(unix))]
 use std::os::windows::fs::FileExt;
 
-macro_rules! print_to_buffer {
-    ($($arg:tt)*) => {
-        unsafe {
-            SCREEN_BUFFER.push(format!($($arg)*));
-        }
-    };
-}
-
-macro_rules! println_to_buffer {
-    ($($arg:tt)*) => {
-        print_to_buffer!($($arg)*);
-        print_to_buffer!("\n");
-    };
-}
-
 lazy_static! {
     static ref SYNTECT_SYNTAX_SET: SyntaxSet = SyntaxSet::load_defaults_newlines();
     static ref SYNTECT_THEME_SET: ThemeSet = ThemeSet::load_defaults();
 }
 
+// the header row of the text viewer is (path, encoding, size), and the last two are 8 and 16 columns wide
+// the table is widened to fit the header, so that the path is never narrower than this
+const MIN_PATH_WIDTH: usize = 16;
+const TEXT_HEADER_FIXED_WIDTH: usize = 8 + 16;
+
 pub fn print_file(
     uid: Uid,
     config: &PrintFileConfig,
 ) -> PrintFileResult {
-    match get_path_by_uid(uid) {
+    match try_get_path_by_uid(uid) {
         Some(path) => {
+            debug_assert_uid_valid!(uid);
             let f_i = get_file_by_uid(uid).unwrap();
             let mut content = vec![];
             let mut truncated = 0;
 
-            match fs::File::open(&path) {
-                Ok(mut f) => if f_i.size <= (1 << 18) {
-                    if let Err(e) = f.read_to_end(&mut content) {
+            // members of an archive are not in the real file system
+            if let Some(member) = read_archive_member(uid) {
+                match member {
+                    Ok(bytes) => {
+                        content = bytes[..bytes.len().min(1 << 18)].to_vec();
+                        truncated = bytes.len() as u64 - content.len() as u64;
+                    },
+                    Err(e) => {
                         print_error_message(
                             Some(f_i),
                             Some(path.to_string()),
-                            format!("{e:?}"),
+                            e.into(),
                             config.min_width,
                             config.max_width,
                         );
                         return PrintFileResult::error();
-                    }
-                } else {
-                    let mut buffer = [0u8; (1 << 18)];
+                    },
+                }
+            }
 
-                    if let Err(e) = f.read_exact(&mut buffer) {
+            else {
+                match fs::File::open(&path) {
+                    Ok(mut f) => if f_i.size <= (1 << 18) {
+                        if let Err(e) = f.read_to_end(&mut content) {
+                            print_error_message(
+                                Some(f_i),
+                                Some(path.to_string()),
+                                e.into(),
+                                config.min_width,
+                                config.max_width,
+                            );
+                            return PrintFileResult::error();
+                        }
+                    } else {
+                        let mut buffer = [0u8; (1 << 18)];
+
+                        if let Err(e) = f.read_exact(&mut buffer) {
+                            print_error_message(
+                                Some(f_i),
+                                Some(path.to_string()),
+                                e.into(),
+                                config.min_width,
+                                config.max_width,
+                            );
+                            return PrintFileResult::error();
+                        }
+
+                        content = buffer.to_vec();
+                        truncated = f_i.size - content.len() as u64;
+                    },
+                    Err(e) => {
                         print_error_message(
                             Some(f_i),
                             Some(path.to_string()),
-                            format!("{e:?}"),
+                            e.into(),
                             config.min_width,
                             config.max_width,
                         );
                         return PrintFileResult::error();
-                    }
-
-                    content = buffer.to_vec();
-                    truncated = f_i.size - content.len() as u64;
-                },
-                Err(e) => {
-                    print_error_message(
-                        Some(f_i),
-                        Some(path.to_string()),
-                        format!("{e:?}"),
-                        config.min_width,
-                        config.max_width,
-                    );
-                    return PrintFileResult::error();
-                },
+                    },
+                }
             }
 
             let mut highlights = config.highlights[..].to_vec();
 
             highlights = highlights.into_iter().filter(|ln| *ln >= config.offset).collect();
 
-            if let Some(text) = try_extract_utf8_text(&content) {
+            let text = match &config.read_mode {
+                FileReadMode::Infer => decode_text(&content, config.encoding),
+                FileReadMode::Force(ViewerKind::Text) => decode_text(&content, config.encoding).or_else(
+                    || Some((String::from_utf8_lossy(&content).to_string(), FileEncoding::Utf8))
+                ),
+                FileReadMode::Force(_) => None,
+            };
+            let can_view_as_image = matches!(
+                config.read_mode,
+                FileReadMode::Infer | FileReadMode::Force(ViewerKind::Image),
+            );
+
+            if let Some((text, encoding)) = text {
                 let lines_in_file = if truncated == 0 {
                     Some(text.lines().count())
                 } else {
@@ -135,6 +162,9 @@ pub fn print_file(
                     vec![LineColor::All(colors::WHITE); 3],
                 ];
 
+                // indexes of `lines`
+                let mut highlighted_rows = HashSet::new();
+
                 let syntax = if let Some(ext) = &config.syntax_highlight {
                     SYNTECT_SYNTAX_SET.find_syntax_by_extension(ext).unwrap_or_else(|| SYNTECT_SYNTAX_SET.find_syntax_plain_text())
                 } else if let Some(ext) = &f_i.file_ext {
@@ -142,7 +172,9 @@ pub fn print_file(
                 } else {
                     SYNTECT_SYNTAX_SET.find_syntax_plain_text()
                 };
-                let mut h = HighlightLines::new(syntax, &SYNTECT_THEME_SET.themes["base16-ocean.dark"]);
+                // unknown themes fall back to the default one
+                let theme = if SYNTAX_THEMES.contains(&config.syntax_theme.as_str()) { config.syntax_theme.as_str() } else { SYNTAX_THEMES[0] };
+                let mut h = HighlightLines::new(syntax, &SYNTECT_THEME_SET.themes[theme]);
                 let mut curr_line_chars = vec![];
                 let mut curr_line_colors = vec![];
                 let mut line_no = 0;
@@ -157,24 +189,37 @@ pub fn print_file(
 
                             if ch == '\n' {
                                 if line_no >= config.offset {
-                                    let (line_no_fmt, line_no_colors) = if highlights.get(0) == Some(&line_no) {
+                                    let is_highlighted = highlights.get(0) == Some(&line_no);
+                                    let (line_no_fmt, line_no_colors) = if is_highlighted {
                                         let line_no_fmt = format!(">>> {line_no}");
                                         let line_no_colors = LineColor::Each(vec![
-                                            vec![colors::RED; 3],
+                                            vec![config.highlight_color; 3],
                                             vec![colors::WHITE; line_no_fmt.len() - 3],
                                         ].concat());
 
                                         highlights = highlights[1..].to_vec();
+                                        highlighted_rows.insert(lines.len());
 
                                         (line_no_fmt, line_no_colors)
                                     } else {
                                         (line_no.to_string(), LineColor::All(colors::WHITE))
                                     };
 
+                                    let curr_line = curr_line_chars.iter().collect::<String>();
+                                    let curr_line_colors = match &config.search_pattern {
+                                        Some(re) if is_highlighted => LineColor::Highlight(
+                                            curr_line_colors,
+                                            re.find_iter(&curr_line).map(
+                                                |m| (m.start(), m.end(), config.highlight_color)
+                                            ).collect(),
+                                        ),
+                                        _ => LineColor::Each(curr_line_colors),
+                                    };
+
                                     lines.push(vec![
                                         line_no_fmt,
                                         String::from("│"),
-                                        curr_line_chars.iter().collect::<String>(),
+                                        curr_line,
                                     ]);
                                     alignments.push(vec![
                                         Alignment::Right,  // line no
@@ -184,7 +229,7 @@ pub fn print_file(
                                     colors.push(vec![
                                         line_no_colors,
                                         LineColor::All(colors::WHITE),  // border
-                                        LineColor::Each(curr_line_colors),
+                                        curr_line_colors,
                                     ]);
                                 }
 
@@ -199,10 +244,21 @@ pub fn print_file(
                                 }
                             }
 
+                            // expands tabs to the next tab stop
+                            else if ch == '\t' {
+                                let tab_width = config.tab_width.max(1);
+                                let spaces = tab_width - (curr_line_chars.len() % tab_width);
+
+                                for _ in 0..spaces {
+                                    curr_line_chars.push(' ');
+                                    curr_line_colors.push(adjust_color_for_background(style.foreground, theme));
+                                }
+                            }
+
                             else {
                                 // tmp hack: it cannot render '\r' characters properly
                                 curr_line_chars.push(if ch == '\r' { ' ' } else { ch });
-                                curr_line_colors.push(convert_ocean_dark_color(style.foreground));
+                                curr_line_colors.push(adjust_color_for_background(style.foreground, theme));
                             }
                         }
                     }
@@ -226,22 +282,43 @@ pub fn print_file(
                     }
                 }
 
+                if config.wrap_lines {
+                    let line_no_width = lines.iter().filter(|line| line.len() == 3).map(|line| line[0].chars().count()).max().unwrap_or(0);
+
+                    // line no, border and content
+                    let max_wrap_width = config.max_width.saturating_sub(line_no_width + 1 + config.column_margin * 4).max(1);
+                    let wrap_width = config.wrap_column.unwrap_or(max_wrap_width).clamp(1, max_wrap_width);
+
+                    wrap_rows(
+                        &mut lines,
+                        &mut alignments,
+                        &mut colors,
+                        &mut highlighted_rows,
+                        wrap_width,
+                        config.max_row,
+                    );
+                }
+
                 if truncated > 0 {
                     lines.push(vec![format!("... (truncated {})", prettify_size(truncated).trim())]);
                     alignments.push(vec![Alignment::Left]);
                     colors.push(vec![LineColor::All(colors::WHITE)]);
                 }
 
+                // 3 columns, so 4 margins
+                let min_header_width = MIN_PATH_WIDTH + TEXT_HEADER_FIXED_WIDTH + config.column_margin * 4;
+                let min_width = config.min_width.max(min_header_width);
+
                 let table_column_widths = calc_table_column_widths(
                     &lines,
-                    Some(config.max_width),
-                    Some(config.min_width),
-                    COLUMN_MARGIN,
+                    Some(config.max_width.max(min_width)),
+                    Some(min_width),
+                    config.column_margin,
                 );
                 let curr_table_width = {
                     let (cols, widths) = table_column_widths.iter().next().unwrap();
 
-                    widths.iter().sum::<usize>() + COLUMN_MARGIN * (*cols + 1)
+                    widths.iter().sum::<usize>() + config.column_margin * (*cols + 1)
                 };
 
                 print_horizontal_line(
@@ -249,27 +326,32 @@ pub fn print_file(
                     curr_table_width,
                     (true, false),
                     (true, true),
+                    None,
                 );
 
                 print_row(
                     colors::BLACK,
                     &vec![
-                        path.clone(),
+                        path.to_string(),
+                        encoding.to_string(),
                         prettify_size(f_i.size),
                     ],
                     &vec![
-                        curr_table_width.max(24) - 16 - COLUMN_MARGIN * 3,
+                        curr_table_width.saturating_sub(TEXT_HEADER_FIXED_WIDTH + config.column_margin * 4).max(MIN_PATH_WIDTH),
+                        8,
                         16,
                     ],
                     &vec![
                         Alignment::Left,
                         Alignment::Right,
+                        Alignment::Right,
                     ],
                     &vec![
                         LineColor::All(colors::WHITE),
+                        LineColor::All(colors::GRAY),
                         LineColor::All(colors::YELLOW),
                     ],
-                    COLUMN_MARGIN,
+                    config.column_margin,
                     (true, true),
                 );
 
@@ -278,18 +360,24 @@ pub fn print_file(
                     curr_table_width,
                     (false, false),
                     (true, true),
+                    None,
                 );
 
                 for (index, line) in lines.iter().enumerate() {
                     let column_widths = table_column_widths.get(&line.len()).unwrap();
+                    let background = if config.highlight_background && highlighted_rows.contains(&index) {
+                        colors::dim(config.highlight_color)
+                    } else {
+                        colors::BLACK
+                    };
 
                     print_row(
-                        colors::BLACK,
+                        background,
                         &line,
                         column_widths,
                         &alignments[index],
                         &colors[index],
-                        COLUMN_MARGIN,
+                        config.column_margin,
                         (true, true),
                     );
                 }
@@ -299,6 +387,7 @@ pub fn print_file(
                     curr_table_width,
                     (false, true),
                     (true, true),
+                    None,
                 );
 
                 println_to_buffer!(
@@ -312,7 +401,7 @@ pub fn print_file(
             }
 
             // image viewer
-            else if let Some(cached_img) = try_read_image(f_i) {
+            else if let Some(cached_img) = can_view_as_image.then(|| try_read_image(f_i)).flatten() {
                 let pixeled_img_w = config.max_width.max(20) - 10;
                 let (real_w, real_h) = (cached_img.w, cached_img.h);
 
@@ -323,24 +412,25 @@ pub fn print_file(
                 let pixeled_img_h = pixeled_img_h * 3 / 4;
 
                 let widths = vec![5, pixeled_img_w];
-                let total_width = 5 + pixeled_img_w + COLUMN_MARGIN;
+                let total_width = 5 + pixeled_img_w + config.column_margin;
 
                 print_horizontal_line(
                     None,
-                    total_width + COLUMN_MARGIN * 2,
+                    total_width + config.column_margin * 2,
                     (true, false),
                     (true, true),
+                    None,
                 );
 
                 print_row(
                     colors::BLACK,
                     &vec![
-                        path.clone(),
+                        path.to_string(),
                         format!("{real_w}X{real_h}"),
                         prettify_size(f_i.size),
                     ],
                     &vec![
-                        total_width.max(40) - 32 - COLUMN_MARGIN * 2,
+                        total_width.max(40).saturating_sub(32 + config.column_margin * 2),
                         16,
                         16,
                     ],
@@ -354,15 +444,16 @@ pub fn print_file(
                         LineColor::All(colors::YELLOW),
                         LineColor::All(colors::YELLOW),
                     ],
-                    COLUMN_MARGIN,
+                    config.column_margin,
                     (true, true),
                 );
 
                 print_horizontal_line(
                     None,
-                    total_width + COLUMN_MARGIN * 2,
+                    total_width + config.column_margin * 2,
                     (false, false),
                     (true, true),
+                    None,
                 );
 
                 // first row: column names
@@ -412,7 +503,7 @@ pub fn print_file(
                         &widths,
                         &row_alignments[i],
                         &row_colors[i],
-                        COLUMN_MARGIN,
+                        config.column_margin,
                         (true, true),
                     );
                 }
@@ -424,16 +515,17 @@ pub fn print_file(
                         &vec![total_width],
                         &vec![Alignment::Left],
                         &vec![LineColor::All(colors::WHITE)],
-                        COLUMN_MARGIN,
+                        config.column_margin,
                         (true, true),
                     );
                 }
 
                 print_horizontal_line(
                     None,
-                    total_width + COLUMN_MARGIN * 2,
+                    total_width + config.column_margin * 2,
                     (false, true),
                     (true, true),
+                    None,
                 );
 
                 println_to_buffer!(
@@ -457,25 +549,35 @@ pub fn print_file(
                 // There's no point in reading more than 16KiB
                 let mut buffer = [0; 16384];
 
-                let read_result = match fs::File::open(&path) {
-                    Ok(f) => {
-                        #[cfg(unix)]
-                        let r = f.read_at(&mut buffer, offset);
+                let read_result = match read_archive_member(uid) {
+                    Some(Ok(bytes)) => {
+                        let start = (offset as usize).min(bytes.len());
+                        let end = (start + buffer.len()).min(bytes.len());
 
-                        #[cfg(not(unix))]
-                        let r = f.seek_read(&mut buffer, offset);
-
-                        r
+                        buffer[..(end - start)].copy_from_slice(&bytes[start..end]);
+                        Ok(end - start)
                     },
-                    Err(e) => {
-                        print_error_message(
-                            Some(f_i),
-                            Some(path.to_string()),
-                            format!("{e:?}"),
-                            config.min_width,
-                            config.max_width,
-                        );
-                        return PrintFileResult::error();
+                    Some(Err(e)) => Err(e),
+                    None => match fs::File::open(&path) {
+                        Ok(f) => {
+                            #[cfg(unix)]
+                            let r = f.read_at(&mut buffer, offset);
+
+                            #[cfg(not(unix))]
+                            let r = f.seek_read(&mut buffer, offset);
+
+                            r
+                        },
+                        Err(e) => {
+                            print_error_message(
+                                Some(f_i),
+                                Some(path.to_string()),
+                                e.into(),
+                                config.min_width,
+                                config.max_width,
+                            );
+                            return PrintFileResult::error();
+                        },
                     },
                 };
 
@@ -487,7 +589,7 @@ pub fn print_file(
                         print_error_message(
                             Some(f_i),
                             Some(path.to_string()),
-                            format!("{e:?}"),
+                            e.into(),
                             config.min_width,
                             config.max_width,
                         );
@@ -497,6 +599,15 @@ pub fn print_file(
 
                 let buffer = buffer[..bytes_read].to_vec();
 
+                // the cursor mode shows the byte at the cursor, instead of the alert
+                let alert = match config.hex_cursor.and_then(|cursor| Some((cursor, *buffer.get(cursor.checked_sub(offset)? as usize)?))) {
+                    Some((cursor, byte)) => format!(
+                        "offset: 0x{cursor:08x}  value: 0x{byte:02x}  char: '{}'  decimal: {byte}",
+                        if (b' '..=b'~').contains(&byte) { byte as char } else { '.' },
+                    ),
+                    None => config.alert.clone(),
+                };
+
                 let (
                     bytes_per_row,
                     total_width,
@@ -506,6 +617,7 @@ pub fn print_file(
                 ) = calc_hex_viewer_row_width(
                     config.min_width,
                     config.max_width,
+                    config.column_margin,
                 );
 
                 let column_widths = vec![
@@ -519,16 +631,17 @@ pub fn print_file(
                     total_width,
                     (true, false),
                     (true, true),
+                    None,
                 );
 
                 print_row(
                     colors::BLACK,
                     &vec![
-                        path.clone(),
+                        path.to_string(),
                         prettify_size(f_i.size),
                     ],
                     &vec![
-                        total_width - 16 - COLUMN_MARGIN * 3,
+                        total_width.saturating_sub(16 + config.column_margin * 3),
                         16,
                     ],
                     &vec![
@@ -539,7 +652,7 @@ pub fn print_file(
                         LineColor::All(colors::WHITE),
                         LineColor::All(colors::YELLOW),
                     ],
-                    COLUMN_MARGIN,
+                    config.column_margin,
                     (true, true),
                 );
 
@@ -548,6 +661,7 @@ pub fn print_file(
                     total_width,
                     (false, false),
                     (true, true),
+                    None,
                 );
 
                 print_row(
@@ -564,12 +678,13 @@ pub fn print_file(
                     ],
                     &vec![Alignment::Center; 3],
                     &vec![LineColor::All(colors::WHITE); 3],
-                    COLUMN_MARGIN,
+                    config.column_margin,
                     (true, true),
                 );
 
                 for (line_no, bytes) in buffer.chunks(bytes_per_row).enumerate() {
                     let mut offset_fmt = format!("{:08x}", offset & 0xffff_ffff);
+                    let mut background = colors::BLACK;
                     let mut offset_color = if offset & 255 == 0 {
                         LineColor::All(colors::GREEN)
                     } else {
@@ -581,7 +696,11 @@ pub fn print_file(
 
                         if offset <= highlight_offset && highlight_offset < offset + bytes_per_row as u64 {
                             offset_fmt = String::from(">>>>>>>>");
-                            offset_color = LineColor::All(colors::RED);
+                            offset_color = LineColor::All(config.highlight_color);
+
+                            if config.highlight_background {
+                                background = colors::dim(config.highlight_color);
+                            }
                         }
 
                         while let Some(highlight_offset) = highlights.get(0) {
@@ -603,28 +722,22 @@ pub fn print_file(
                     let mut ascii_colors = vec![];
 
                     for (index, byte) in bytes.iter().enumerate() {
-                        bytes_fmt.push(format!("{byte:02x}"));
+                        let byte_color = colorize_byte(*byte);
 
-                        if *byte == 0 {
-                            bytes_colors.push(colors::GRAY);
-                            bytes_colors.push(colors::GRAY);
-                        }
-
-                        else {
-                            bytes_colors.push(colors::YELLOW);
-                            bytes_colors.push(colors::YELLOW);
-                        }
+                        bytes_fmt.push(format!("{byte:02x}"));
+                        bytes_colors.push(byte_color);
+                        bytes_colors.push(byte_color);
 
                         if b' ' <= *byte && *byte <= b'~' {
                             ascii_fmt.push((*byte as char).to_string());
-                            ascii_colors.push(colors::YELLOW);
                         }
 
                         else {
                             ascii_fmt.push(".".to_string());
-                            ascii_colors.push(colors::GRAY);
                         }
 
+                        ascii_colors.push(byte_color);
+
                         if index == bytes.len() - 1 {
                             // nop
                         }
@@ -648,8 +761,28 @@ pub fn print_file(
                     let bytes_fmt = bytes_fmt.concat();
                     let ascii_fmt = ascii_fmt.concat();
 
+                    // the byte at the cursor has inverted colors
+                    // there's an extra space after every 8 bytes
+                    let (bytes_line_color, ascii_line_color) = match config.hex_cursor {
+                        Some(cursor) if offset <= cursor && cursor < offset + bytes.len() as u64 => {
+                            let index = (cursor - offset) as usize;
+                            let hex_index = index * 3 + index / 8;
+                            let ascii_index = index + index / 8 * 2;
+
+                            bytes_colors[hex_index] = colors::BLACK;
+                            bytes_colors[hex_index + 1] = colors::BLACK;
+                            ascii_colors[ascii_index] = colors::BLACK;
+
+                            (
+                                LineColor::Highlight(bytes_colors, vec![(hex_index, hex_index + 2, colors::WHITE)]),
+                                LineColor::Highlight(ascii_colors, vec![(ascii_index, ascii_index + 1, colors::WHITE)]),
+                            )
+                        },
+                        _ => (LineColor::Each(bytes_colors), LineColor::Each(ascii_colors)),
+                    };
+
                     print_row(
-                        colors::BLACK,
+                        background,
                         &vec![
                             offset_fmt,
                             bytes_fmt,
@@ -659,10 +792,10 @@ pub fn print_file(
                         &vec![Alignment::Right, Alignment::Left, Alignment::Left],
                         &vec![
                             offset_color,
-                            LineColor::Each(bytes_colors),
-                            LineColor::Each(ascii_colors),
+                            bytes_line_color,
+                            ascii_line_color,
                         ],
-                        COLUMN_MARGIN,
+                        config.column_margin,
                         (true, true),
                     );
 
@@ -679,10 +812,10 @@ pub fn print_file(
                     print_row(
                         colors::BLACK,
                         &vec![format!("... (truncated {})", prettify_size(truncated_bytes).trim())],
-                        &vec![total_width - COLUMN_MARGIN * 2],
+                        &vec![total_width.saturating_sub(config.column_margin * 2)],
                         &vec![Alignment::Left],
                         &vec![LineColor::All(colors::WHITE)],
-                        COLUMN_MARGIN,
+                        config.column_margin,
                         (true, true),
                     );
                 }
@@ -692,12 +825,13 @@ pub fn print_file(
                     total_width,
                     (false, true),
                     (true, true),
+                    None,
                 );
 
                 println_to_buffer!(
                     "{}{}{}",
-                    config.alert,
-                    if !config.alert.is_empty() && config.show_elapsed_time { ": " } else { "" },
+                    alert,
+                    if !alert.is_empty() && config.show_elapsed_time { ": " } else { "" },
                     if config.show_elapsed_time { format!("took {}", format_duration(Instant::now().duration_since(config.elapsed_timer.clone()))) } else { String::new() },
                 );
 
@@ -708,7 +842,7 @@ pub fn print_file(
             print_error_message(
                 None,
                 None,
-                format!("get_path_by_uid({}) has failed", uid.debug_info()),
+                FileQueryError::PathNotFound(uid),
                 config.min_width,
                 config.max_width,
             );
@@ -718,21 +852,23 @@ pub fn print_file(
     }
 }
 
+// widths without the margins
 // '  00000000  7f 45 4c 46  .ELF  '
-const HEX_VIEWER_4_BYTES: usize = 23 + 4 * COLUMN_MARGIN;
+const HEX_VIEWER_4_BYTES: usize = 23;
 
 // '  00000000  7f 45 4c 46 02 01 01 00  .ELF....  '
-const HEX_VIEWER_8_BYTES: usize = 39 + 4 * COLUMN_MARGIN;
+const HEX_VIEWER_8_BYTES: usize = 39;
 
 // '  00000000  7f 45 4c 46 02 01 01 00  00 00 00 00 00 00 00 00  .ELF....  ........  '
-const HEX_VIEWER_16_BYTES: usize = 74 + 4 * COLUMN_MARGIN;
+const HEX_VIEWER_16_BYTES: usize = 74;
 
 // '  00000000  7f 45 4c 46 02 01 01 00  00 00 00 00 00 00 00 00  03 00 3e 00 01 00 00 00  a0 a1 03 00 00 00 00 00  .ELF....  ........  ..>.....  ........  '
-const HEX_VIEWER_32_BYTES: usize = 144 + 4 * COLUMN_MARGIN;
+const HEX_VIEWER_32_BYTES: usize = 144;
 
 fn calc_hex_viewer_row_width(
     min_width: usize,
     max_width: usize,
+    margin: usize,
 ) -> (
     usize,  // bytes per row
     usize,  // total width
@@ -740,19 +876,114 @@ fn calc_hex_viewer_row_width(
     usize,  // col2 width
     usize,  // col3 width
 ) {
-    if max_width < HEX_VIEWER_8_BYTES {
-        (4, HEX_VIEWER_4_BYTES, 8, 11, 4)
+    // a row has 3 columns, so there are 4 margins
+    let margins = 4 * margin;
+
+    if max_width < HEX_VIEWER_8_BYTES + margins {
+        (4, HEX_VIEWER_4_BYTES + margins, 8, 11, 4)
     }
 
-    else if max_width < HEX_VIEWER_16_BYTES {
-        (8, HEX_VIEWER_8_BYTES, 8, 23, 8)
+    else if max_width < HEX_VIEWER_16_BYTES + margins {
+        (8, HEX_VIEWER_8_BYTES + margins, 8, 23, 8)
     }
 
-    else if max_width < HEX_VIEWER_32_BYTES {
-        (16, HEX_VIEWER_16_BYTES, 8, 48, 18)
+    else if max_width < HEX_VIEWER_32_BYTES + margins {
+        (16, HEX_VIEWER_16_BYTES + margins, 8, 48, 18)
     }
 
     else {
-        (32, HEX_VIEWER_32_BYTES, 8, 98, 38)
+        (32, HEX_VIEWER_32_BYTES + margins, 8, 98, 38)
+    }
+}
+
+// it splits the content (the last column) of the rows of the text viewer, so that it's at most `width` characters
+// the continued rows don't have line numbers, and they're highlighted if the original row is
+// the first row (header) and the rows with less than 3 columns are not touched
+// it stops after `max_rows` rows of contents, so that the wrapped lines don't overflow the terminal
+fn wrap_rows(
+    lines: &mut Vec<Vec<String>>,
+    alignments: &mut Vec<Vec<Alignment>>,
+    colors: &mut Vec<Vec<LineColor>>,
+    highlighted_rows: &mut HashSet<usize>,
+    width: usize,
+    max_rows: usize,
+) {
+    let mut new_lines = vec![];
+    let mut new_alignments = vec![];
+    let mut new_colors = vec![];
+    let mut new_highlighted_rows = HashSet::new();
+
+    let rows = std::mem::take(lines).into_iter().zip(std::mem::take(alignments)).zip(std::mem::take(colors));
+
+    for (index, ((line, alignment), color)) in rows.enumerate() {
+        if index == 0 || line.len() < 3 {
+            new_lines.push(line);
+            new_alignments.push(alignment);
+            new_colors.push(color);
+            continue;
+        }
+
+        let content_len = line[2].chars().count();
+        let mut start = 0;
+
+        // an empty line is still a row
+        while start < content_len || start == 0 {
+            if new_lines.len() > max_rows {
+                break;
+            }
+
+            let end = (start + width).min(content_len);
+
+            if highlighted_rows.contains(&index) {
+                new_highlighted_rows.insert(new_lines.len());
+            }
+
+            new_lines.push(vec![
+                if start == 0 { line[0].clone() } else { String::new() },
+                line[1].clone(),
+                line[2].chars().skip(start).take(end - start).collect(),
+            ]);
+            new_alignments.push(alignment.clone());
+            new_colors.push(vec![
+                if start == 0 { color[0].clone() } else { LineColor::All(colors::WHITE) },
+                color[1].clone(),
+                split_line_color(&color[2], &line[2], start, end),
+            ]);
+
+            if end == content_len {
+                break;
+            }
+
+            start = end;
+        }
+    }
+
+    *lines = new_lines;
+    *alignments = new_alignments;
+    *colors = new_colors;
+    *highlighted_rows = new_highlighted_rows;
+}
+
+// colors of `content[start..end]`, where `start` and `end` are char indexes
+fn split_line_color(color: &LineColor, content: &str, start: usize, end: usize) -> LineColor {
+    match color {
+        LineColor::All(c) => LineColor::All(*c),
+        LineColor::Each(colors) => LineColor::Each(colors[start..end].to_vec()),
+        LineColor::Highlight(colors, ranges) => {
+            let byte_index = |char_index: usize| content.char_indices().nth(char_index).map(|(i, _)| i).unwrap_or(content.len());
+            let (byte_start, byte_end) = (byte_index(start), byte_index(end));
+
+            LineColor::Highlight(
+                colors[start..end].to_vec(),
+                ranges.iter().filter_map(
+                    |(range_start, range_end, c)| {
+                        let range_start = (*range_start).max(byte_start);
+                        let range_end = (*range_end).min(byte_end);
+
+                        if range_start < range_end { Some((range_start - byte_start, range_end - byte_start, *c)) } else { None }
+                    }
+                ).collect(),
+            )
+        },
     }
 }
@@ -3,31 +3,32 @@ use super::{
     print_horizontal_line,
     print_row,
     Alignment,
-    COLUMN_MARGIN,
     LineColor,
 };
-use super::config::PrintLinkConfig;
+use super::config::{FileEncoding, PrintDirConfig, PrintFileConfig, PrintLinkConfig};
+use super::dir::print_dir;
+use super::file::print_file;
 use super::result::PrintLinkResult;
-use super::utils::prettify_size;
+use super::utils::{decode_text, format_duration, prettify_size};
+use colored::Colorize;
 use crate::colors;
+use crate::error::FileQueryError;
+use crate::file::{File, FileType};
 use crate::uid::Uid;
-use crate::utils::{get_file_by_uid, get_path_by_uid};
+use crate::utils::{get_file_by_uid, is_uid_registered, try_get_path_by_uid};
 use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+use std::time::Instant;
 
-// macro_rules! print_to_buffer {
-//     ($($arg:tt)*) => {
-//         unsafe {
-//             SCREEN_BUFFER.push(format!($($arg)*));
-//         }
-//     };
-// }
-
-// macro_rules! println_to_buffer {
-//     ($($arg:tt)*) => {
-//         print_to_buffer!($($arg)*);
-//         print_to_buffer!("\n");
-//     };
-// }
+// it gives up following a chain of links longer than this
+const MAX_LINK_HOPS: usize = 40;
+
+// the preview of the target needs at least this many rows
+const MIN_ROWS_FOR_TARGET_PREVIEW: usize = 16;
+
+// `is_text_file` reads this many bytes
+const TEXT_DETECTION_BYTES: u64 = 4096;
 
 pub fn print_link(
     uid: Uid,
@@ -39,7 +40,7 @@ pub fn print_link(
             print_error_message(
                 None,
                 None,
-                format!("get_file_by_uid({}) has failed", uid.debug_info()),
+                FileQueryError::FileNotFound(uid),
                 config.min_width,
                 config.max_width,
             );
@@ -47,26 +48,27 @@ pub fn print_link(
         },
     };
 
-    match get_path_by_uid(uid) {
+    match try_get_path_by_uid(uid) {
         Some(path) => match fs::read_link(path) {
             Ok(dest) => {
                 let dest = dest.display().to_string();
-                let table_width = (dest.len() + COLUMN_MARGIN * 2).max(path.len() + 16 + COLUMN_MARGIN * 3).min(config.max_width).max(config.min_width);
+                let table_width = (dest.len() + config.column_margin * 2).max(path.len() + 16 + config.column_margin * 3).min(config.max_width).max(config.min_width);
 
                 print_horizontal_line(
                     None,
                     table_width,
                     (true, false),
                     (true, true),
+                    None,
                 );
                 print_row(
                     colors::BLACK,
                     &vec![
-                        path.clone(),
+                        path.to_string(),
                         prettify_size(f_i.size),
                     ],
                     &vec![
-                        table_width - 16 - COLUMN_MARGIN * 3,
+                        table_width.saturating_sub(16 + config.column_margin * 3),
                         16,
                     ],
                     &vec![
@@ -77,7 +79,7 @@ pub fn print_link(
                         LineColor::All(colors::WHITE),
                         LineColor::All(colors::YELLOW),
                     ],
-                    COLUMN_MARGIN,
+                    config.column_margin,
                     (true, true),
                 );
                 print_row(
@@ -86,7 +88,7 @@ pub fn print_link(
                         dest,
                     ],
                     &vec![
-                        table_width - COLUMN_MARGIN * 2,
+                        table_width.saturating_sub(config.column_margin * 2),
                     ],
                     &vec![
                         Alignment::Left,
@@ -94,7 +96,7 @@ pub fn print_link(
                     &vec![
                         LineColor::All(colors::WHITE),
                     ],
-                    COLUMN_MARGIN,
+                    config.column_margin,
                     (true, true),
                 );
                 print_horizontal_line(
@@ -102,15 +104,38 @@ pub fn print_link(
                     table_width,
                     (false, true),
                     (true, true),
+                    None,
+                );
+
+                // `fs::metadata` follows all the links in the chain
+                let target_type = match fs::metadata(path) {
+                    Ok(m) if m.is_dir() => Some(FileType::Dir),
+                    Ok(_) => Some(FileType::File),
+
+                    // broken link, or too many levels of links
+                    Err(_) => None,
+                };
+                if config.show_target_preview && config.max_row >= MIN_ROWS_FOR_TARGET_PREVIEW {
+                    print_target_preview(path, config);
+                }
+
+                let result = PrintLinkResult::success(target_type, get_link_chain_length(path));
+                let alert = if config.alert.is_empty() { result.describe() } else { config.alert.clone() };
+
+                println_to_buffer!(
+                    "{}{}{}",
+                    alert,
+                    if config.show_elapsed_time { ": " } else { "" },
+                    if config.show_elapsed_time { format!("took {}", format_duration(Instant::now().duration_since(config.elapsed_timer))) } else { String::new() },
                 );
 
-                PrintLinkResult::success()
+                result
             },
             Err(e) => {
                 print_error_message(
                     Some(f_i),
                     Some(path.to_string()),
-                    format!("{e:?}"),
+                    e.into(),
                     config.min_width,
                     config.max_width,
                 );
@@ -121,7 +146,7 @@ pub fn print_link(
             print_error_message(
                 Some(f_i),
                 None,
-                format!("get_path_by_uid({}) has failed", uid.debug_info()),
+                FileQueryError::PathNotFound(uid),
                 config.min_width,
                 config.max_width,
             );
@@ -129,3 +154,91 @@ pub fn print_link(
         },
     }
 }
+
+// a text file shows its first lines, and a dir shows its first entries
+// it shows nothing for the other files
+fn print_target_preview(path: &str, config: &PrintLinkConfig) {
+    let target = match fs::canonicalize(path) {
+        Ok(target) => target,
+        Err(_) => {
+            println_to_buffer!("{}", "<<broken>>".color(colors::RED));
+            return;
+        },
+    };
+    let target_path = target.to_string_lossy().to_string();
+
+    // the target might already be registered, with its children and parent
+    let uid = Uid::from_path(&target_path);
+
+    if !is_uid_registered(uid) {
+        File::new_from_path_buf(target, Some(uid), None);
+    }
+
+    let target_file = match get_file_by_uid(uid) {
+        Some(f) if !f.is_special_file() => f,
+        _ => {
+            println_to_buffer!("{}", "<<broken>>".color(colors::RED));
+            return;
+        },
+    };
+
+    if target_file.is_dir() {
+        print_dir(
+            uid,
+            &PrintDirConfig {
+                max_row: config.max_row / 2,
+                max_width: config.max_width,
+                min_width: config.min_width,
+                column_margin: config.column_margin,
+                show_elapsed_time: false,
+                ..PrintDirConfig::default()
+            },
+        );
+    }
+
+    else if target_file.is_file() && is_text_file(&target_path) {
+        print_file(
+            uid,
+            &PrintFileConfig {
+                max_row: config.max_row / 2,
+                max_width: config.max_width,
+                min_width: config.min_width,
+                column_margin: config.column_margin,
+                show_elapsed_time: false,
+                ..PrintFileConfig::default()
+            },
+        );
+    }
+}
+
+fn is_text_file(path: &str) -> bool {
+    let mut content = vec![];
+
+    match fs::File::open(path) {
+        Ok(f) => f.take(TEXT_DETECTION_BYTES).read_to_end(&mut content).is_ok() && decode_text(&content, FileEncoding::Auto).is_some(),
+        Err(_) => false,
+    }
+}
+
+fn get_link_chain_length(path: &str) -> usize {
+    let mut curr_path = PathBuf::from(path);
+    let mut result = 0;
+
+    while result < MAX_LINK_HOPS {
+        match fs::read_link(&curr_path) {
+            Ok(dest) => {
+                // a relative destination is relative to the dir of the link
+                curr_path = match curr_path.parent() {
+                    Some(parent) => parent.join(dest),
+                    None => dest,
+                };
+                result += 1;
+            },
+            Err(_) => {
+                break;
+            },
+        }
+    }
+
+    result
+}
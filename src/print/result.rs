@@ -1,3 +1,5 @@
+use crate::file::FileType;
+
 pub struct PrintDirResult {}
 
 impl PrintDirResult {
@@ -84,19 +86,55 @@ impl PrintFileResult {
     }
 }
 
-pub struct PrintLinkResult {}
+pub struct PrintLinkResult {
+    // `false` if the link is broken
+    pub target_exists: bool,
+
+    // type of the final destination (it follows all the links in the chain)
+    // `None` if the link is broken
+    pub target_type: Option<FileType>,
+
+    // number of links until the final destination
+    // it's 1 if the link points to a non-link file
+    pub chain_length: usize,
+}
 
 impl PrintLinkResult {
-    pub fn success() -> Self {
-        PrintLinkResult {}
+    pub fn success(target_type: Option<FileType>, chain_length: usize) -> Self {
+        PrintLinkResult {
+            target_exists: target_type.is_some(),
+            target_type,
+            chain_length,
+        }
     }
 
     // you MUST NOT read any of these value
     pub fn dummy() -> Self {
-        PrintLinkResult {}
+        PrintLinkResult {
+            target_exists: false,
+            target_type: None,
+            chain_length: 0,
+        }
     }
 
     pub fn error() -> Self {
-        PrintLinkResult {}
+        PrintLinkResult::dummy()
+    }
+
+    // "→ directory, 2 hops"
+    pub fn describe(&self) -> String {
+        format!(
+            "→ {}, {} hop{}",
+            match self.target_type {
+                Some(FileType::Dir) => "directory",
+                Some(FileType::File) => "file",
+                Some(FileType::Symlink) => "link",
+                #[cfg(unix)]
+                Some(_) => "special file",
+                None => "broken link",
+            },
+            self.chain_length,
+            if self.chain_length == 1 { "" } else { "s" },
+        )
     }
 }
@@ -0,0 +1,186 @@
+use super::{
+    calc_table_column_widths,
+    print_error_message,
+    print_horizontal_line,
+    print_row,
+    Alignment,
+    LineColor,
+};
+use super::config::{PermissionsFormat, PrintDirConfig};
+use super::utils::{guess_mime_type, prettify_permissions, prettify_size, prettify_time};
+use crate::colors;
+use crate::error::FileQueryError;
+use crate::tags::get_tag;
+use crate::uid::Uid;
+use crate::utils::{filetime_to_system_time, get_file_by_uid, try_get_path_by_uid};
+use std::fs;
+use std::time::SystemTime;
+
+#[cfg(unix)]
+use std::os::unix::fs::MetadataExt;
+
+// values longer than this are truncated
+const MAX_STAT_VALUE_LEN: usize = 256;
+
+// like `stat(1)`, it shows all the metadata of the file, in (field, value) rows
+// the fields that are not cached yet are computed here
+pub fn print_stat(uid: Uid, config: &PrintDirConfig) {
+    let file = match get_file_by_uid(uid) {
+        Some(file) => file,
+        None => {
+            print_error_message(
+                None,
+                None,
+                FileQueryError::FileNotFound(uid),
+                config.min_width,
+                config.max_width,
+            );
+            return;
+        },
+    };
+    let path = try_get_path_by_uid(uid).unwrap_or("");
+    let metadata = fs::symlink_metadata(path).ok();
+    let now = SystemTime::now();
+    let mut rows: Vec<(&str, String)> = vec![];
+
+    rows.push(("name", file.name.clone()));
+    rows.push(("path", path.to_string()));
+    rows.push(("type", file.file_type.to_string()));
+    rows.push(("mime type", guess_mime_type(file.file_type, file.file_ext.as_deref()).to_string()));
+    rows.push(("size", format!("{} ({} bytes)", prettify_size(file.size).trim(), file.size)));
+
+    if file.is_dir() {
+        rows.push(("total size", prettify_size(file.get_recursive_size_blocking()).trim().to_string()));
+    }
+
+    if let Some(mode) = file.mode {
+        rows.push(("permissions", format!(
+            "{} ({})",
+            prettify_permissions(mode, PermissionsFormat::Symbolic),
+            prettify_permissions(mode, PermissionsFormat::Octal),
+        )));
+    }
+
+    #[cfg(unix)]
+    if let Some(metadata) = &metadata {
+        let owner = nix::unistd::User::from_uid(metadata.uid().into()).ok().flatten().map(|user| user.name);
+        let group = nix::unistd::Group::from_gid(metadata.gid().into()).ok().flatten().map(|group| group.name);
+
+        rows.push(("owner", format!("{} ({})", owner.unwrap_or(String::from("?")), metadata.uid())));
+        rows.push(("group", format!("{} ({})", group.unwrap_or(String::from("?")), metadata.gid())));
+    }
+
+    if let Some((dev, inode)) = file.inode {
+        rows.push(("inode", format!("{inode} (device {dev})")));
+    }
+
+    rows.push(("hard links", file.nlink.to_string()));
+
+    if let Some(created_at) = file.created_at {
+        rows.push(("created", prettify_time(&now, filetime_to_system_time(created_at))));
+    }
+
+    rows.push(("modified", prettify_time(&now, file.last_modified)));
+
+    if let Some(Ok(accessed)) = metadata.as_ref().map(|m| m.accessed()) {
+        rows.push(("accessed", prettify_time(&now, accessed)));
+    }
+
+    if let Some(checksum) = file.get_checksum() {
+        rows.push(("sha256", checksum.iter().map(|b| format!("{b:02x}")).collect()));
+    }
+
+    if let Some(Some((lines, words, bytes))) = file.wc {
+        rows.push(("word count", format!("{lines} lines, {words} words, {bytes} bytes")));
+    }
+
+    if let Some(tag) = get_tag(path) {
+        rows.push(("tag", tag.to_string()));
+    }
+
+    #[cfg(unix)]
+    if let Ok(keys) = xattr::list(path) {
+        for key in keys {
+            let key = key.to_string_lossy().to_string();
+            let value = xattr::get(path, &key).ok().flatten().map(|v| String::from_utf8_lossy(&v).to_string()).unwrap_or_default();
+            rows.push(("xattr", format!("{key}={value}")));
+        }
+    }
+
+    rows.push(("hidden", file.is_hidden.to_string()));
+    rows.push(("network mount", file.is_network_mount.to_string()));
+
+    let rows = rows.into_iter().map(
+        |(field, value)| vec![
+            field.to_string(),
+            String::from("│"),
+            value.chars().take(MAX_STAT_VALUE_LEN).collect::<String>(),
+        ]
+    ).collect::<Vec<_>>();
+
+    let column_widths = calc_table_column_widths(
+        &rows,
+        Some(config.max_width),
+        Some(config.min_width),
+        config.column_margin,
+    );
+    let column_widths = column_widths.get(&3).unwrap();
+    let table_width = column_widths.iter().sum::<usize>() + config.column_margin * 2;
+
+    print_horizontal_line(
+        None,
+        table_width + config.column_margin * 2,
+        (true, false),
+        (true, true),
+        None,
+    );
+    print_row(
+        colors::BLACK,
+        &vec![format!("stat of {}", file.name)],
+        &vec![table_width],
+        &vec![Alignment::Center],
+        &vec![LineColor::All(colors::WHITE)],
+        config.column_margin,
+        (true, true),
+    );
+    print_horizontal_line(
+        None,
+        table_width + config.column_margin * 2,
+        (false, false),
+        (true, true),
+        None,
+    );
+
+    for row in rows.iter() {
+        print_row(
+            colors::BLACK,
+            row,
+            column_widths,
+            &vec![Alignment::Right, Alignment::Left, Alignment::Left],
+            &vec![
+                LineColor::All(colors::GRAY),   // field
+                LineColor::All(colors::WHITE),  // border
+                LineColor::All(colors::WHITE),  // value
+            ],
+            config.column_margin,
+            (true, true),
+        );
+    }
+
+    print_horizontal_line(
+        None,
+        table_width + config.column_margin * 2,
+        (false, true),
+        (true, true),
+        None,
+    );
+    print_row(
+        colors::BLACK,
+        &vec![String::from("press any key to close")],
+        &vec![table_width + config.column_margin * 2],
+        &vec![Alignment::Left],
+        &vec![LineColor::All(colors::GRAY)],
+        0,
+        (false, false),
+    );
+}
@@ -1,12 +1,20 @@
 use colored::Color;
+use super::config::{FileEncoding, PermissionsFormat};
+use crate::archive::read_archive_member;
 use crate::colors;
 use crate::file::{File, FileType};
 use crate::uid::Uid;
-use crate::utils::get_path_by_uid;
+use crate::utils::try_get_path_by_uid;
 use image::RgbImage;
 use image::io::{Reader as ImageReader};
+use lazy_static::lazy_static;
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, SystemTime};
 use syntect::highlighting::Color as SyColor;
+use unicode_segmentation::UnicodeSegmentation;
 
 // the result must be right-aligned
 pub fn prettify_size(size: u64) -> String {
@@ -31,8 +39,44 @@ pub fn prettify_size(size: u64) -> String {
     }
 }
 
+// "1,048,576"
+pub fn format_exact_size(size: u64) -> String {
+    let digits = size.to_string().chars().rev().collect::<Vec<_>>();
+
+    digits.chunks(3).map(
+        |chunk| chunk.iter().collect::<String>()
+    ).collect::<Vec<_>>().join(",").chars().rev().collect()
+}
+
+// "+1 KiB", "-500 B", " 0 B" or "new"
+pub fn prettify_size_delta(delta: Option<i64>) -> String {
+    match delta {
+        Some(delta) if delta > 0 => format!("+{}", prettify_size(delta as u64)),
+        Some(delta) if delta < 0 => format!("-{}", prettify_size(delta.unsigned_abs())),
+        Some(_) => format!(" {}", prettify_size(0)),
+        None => String::from("new"),
+    }
+}
+
+// "142 elements" if it shows from the start
+// "showing 21–80 of 142" otherwise (1-based, inclusive)
+pub fn format_element_range(offset: usize, shown: usize, total: usize) -> String {
+    if offset == 0 || shown == 0 {
+        format!("{total} elements")
+    }
+
+    else {
+        format!("showing {}–{} of {total}", offset + 1, offset + shown)
+    }
+}
+
 pub fn prettify_time(now: &SystemTime, time: SystemTime) -> String {
-    let duration = now.duration_since(time).unwrap();
+    // clocks of network file systems might be wrong
+    if time > *now {
+        return String::from("in the future");
+    }
+
+    let duration = now.duration_since(time).unwrap_or_default();
     let secs = duration.as_secs();
 
     if secs < 5 {
@@ -71,6 +115,100 @@ pub fn prettify_time(now: &SystemTime, time: SystemTime) -> String {
     }
 }
 
+// it only knows common extensions, it doesn't read the contents
+pub fn guess_mime_type(file_type: FileType, ext: Option<&str>) -> &'static str {
+    if file_type == FileType::Dir {
+        return "inode/directory";
+    }
+
+    if file_type == FileType::Symlink {
+        return "inode/symlink";
+    }
+
+    match ext.map(|ext| ext.to_ascii_lowercase()).as_deref() {
+        Some("txt" | "md" | "rs" | "py" | "c" | "h" | "cpp" | "toml" | "yaml" | "yml" | "ini" | "log") => "text/plain",
+        Some("html" | "htm") => "text/html",
+        Some("css") => "text/css",
+        Some("csv") => "text/csv",
+        Some("js") => "text/javascript",
+        Some("json") => "application/json",
+        Some("xml") => "application/xml",
+        Some("pdf") => "application/pdf",
+        Some("zip") => "application/zip",
+        Some("gz") => "application/gzip",
+        Some("tar") => "application/x-tar",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("svg") => "image/svg+xml",
+        Some("mp3") => "audio/mpeg",
+        Some("wav") => "audio/wav",
+        Some("mp4") => "video/mp4",
+        Some("webm") => "video/webm",
+        _ => "application/octet-stream",
+    }
+}
+
+// first 8 hex digits
+pub fn prettify_checksum(checksum: &[u8; 32]) -> String {
+    checksum[..4].iter().map(|b| format!("{b:02x}")).collect()
+}
+
+// `rwxr-xr--` or `754`, like `ls -l` and `stat -c %a`
+// setuid, setgid and sticky bits are `s` and `t` in the symbolic format
+pub fn prettify_permissions(mode: u32, format: PermissionsFormat) -> String {
+    match format {
+        PermissionsFormat::Octal => if mode & 0o7000 != 0 {
+            format!("{:04o}", mode & 0o7777)
+        } else {
+            format!("{:03o}", mode & 0o777)
+        },
+        PermissionsFormat::Symbolic => {
+            let mut result = String::with_capacity(9);
+
+            // (shift, special bit, special char)
+            for (shift, special_bit, special_char) in [(6, 0o4000, 's'), (3, 0o2000, 's'), (0, 0o1000, 't')] {
+                let bits = (mode >> shift) & 0o7;
+
+                result.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+                result.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+                result.push(match (bits & 0o1 != 0, mode & special_bit != 0) {
+                    (true, true) => special_char,
+                    (false, true) => special_char.to_ascii_uppercase(),
+                    (true, false) => 'x',
+                    (false, false) => '-',
+                });
+            }
+
+            result
+        },
+    }
+}
+
+// color of each char of the symbolic format
+pub fn colorize_permission_char(c: char) -> Color {
+    match c {
+        'x' | 's' | 't' => colors::YELLOW,
+        '-' => colors::GRAY,
+        _ => colors::WHITE,
+    }
+}
+
+// relative to `Uid::LAUNCH`
+// if the file is not inside `Uid::LAUNCH`, it returns the absolute path
+pub fn get_relative_path(uid: Uid) -> Option<String> {
+    let path = try_get_path_by_uid(uid)?;
+
+    match try_get_path_by_uid(Uid::LAUNCH) {
+        Some(base) => match Path::new(path).strip_prefix(base) {
+            Ok(relative_path) => Some(relative_path.to_string_lossy().to_string()),
+            Err(_) => Some(path.to_string()),
+        },
+        None => Some(path.to_string()),
+    }
+}
+
 pub fn colorize_name(_: FileType, is_executable: bool) -> Color {
     if is_executable {
         colors::YELLOW
@@ -86,6 +224,13 @@ pub fn colorize_type(ty: FileType) -> Color {
         FileType::File => colors::WHITE,
         FileType::Dir => colors::GREEN,
         FileType::Symlink => colors::YELLOW,
+        #[cfg(unix)]
+        FileType::Device => colors::BLUE,
+        #[cfg(unix)]
+        FileType::Fifo
+        | FileType::Socket => colors::MAGENTA,
+        #[cfg(unix)]
+        FileType::Unknown => colors::GRAY,
     }
 }
 
@@ -107,8 +252,95 @@ pub fn colorize_size(size: u64) -> Color {
     }
 }
 
+pub fn colorize_byte(b: u8) -> Color {
+    match b {
+        0x00 => colors::GRAY,
+        0x20..=0x7e => colors::WHITE,
+        0x01..=0x1f | 0x7f => colors::RED,
+        0xff => colors::CYAN,
+        0x80..=0xfe => colors::BLUE,
+    }
+}
+
+pub fn colorize_size_delta(delta: Option<i64>) -> Color {
+    match delta {
+        Some(delta) if delta > 0 => colors::RED,
+        Some(delta) if delta < 0 => colors::GREEN,
+        Some(_) => colors::GRAY,
+        None => colors::WHITE,
+    }
+}
+
+pub fn colorize_depth(depth: usize) -> Color {
+    if depth < 2 {
+        colors::GREEN
+    }
+
+    else if depth < 5 {
+        colors::WHITE
+    }
+
+    else if depth < 10 {
+        colors::YELLOW
+    }
+
+    else {
+        colors::RED
+    }
+}
+
+pub fn colorize_disk_usage(used_ratio: f64) -> Color {
+    if used_ratio < 0.7 {
+        colors::GREEN
+    }
+
+    else if used_ratio < 0.9 {
+        colors::YELLOW
+    }
+
+    else {
+        colors::RED
+    }
+}
+
+pub fn categorize_age(secs: u64) -> &'static str {
+    if secs < 24 * 60 * 60 {
+        "today"
+    }
+
+    else if secs < 7 * 24 * 60 * 60 {
+        "this week"
+    }
+
+    else if secs < 30 * 24 * 60 * 60 {
+        "this month"
+    }
+
+    else if secs < 365 * 24 * 60 * 60 {
+        "this year"
+    }
+
+    else {
+        "old"
+    }
+}
+
+pub fn colorize_age(secs: u64) -> Color {
+    match categorize_age(secs) {
+        "today" => colors::GREEN,
+        "this week" => colors::WHITE,
+        "this month" => colors::YELLOW,
+        "this year" => colors::RED,
+        _ => colors::GRAY,
+    }
+}
+
 pub fn colorize_time(now: &SystemTime, time: SystemTime) -> Color {
-    let duration = now.duration_since(time).unwrap();
+    if time > *now {
+        return colors::BLUE;
+    }
+
+    let duration = now.duration_since(time).unwrap_or_default();
     let secs = duration.as_secs();
 
     if secs < 99 {
@@ -128,6 +360,21 @@ pub fn colorize_time(now: &SystemTime, time: SystemTime) -> Color {
     }
 }
 
+// how long it took to render
+pub fn colorize_duration(duration: Duration) -> Color {
+    if duration.as_millis() < 100 {
+        colors::GRAY
+    }
+
+    else if duration.as_millis() <= 1000 {
+        colors::YELLOW
+    }
+
+    else {
+        colors::RED
+    }
+}
+
 pub fn try_extract_utf8_text(content: &[u8]) -> Option<String> {
     if content.len() < 6 {
         String::from_utf8(content.to_vec()).ok()
@@ -155,40 +402,96 @@ pub fn try_extract_utf8_text(content: &[u8]) -> Option<String> {
     }
 }
 
-pub fn try_read_image(file: &File) -> Option<&CachedImage> {
-    for (uid_, img) in unsafe { IMAGE_CACHE.iter() } {
-        if *uid_ == file.uid {
-            return Some(img);
+// characters of 0x80..=0x9f in windows-1252
+// the other bytes are the same as latin-1
+const CP1252_HIGH: [char; 32] = [
+    '€', '\u{fffd}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{fffd}', 'Ž', '\u{fffd}',
+    '\u{fffd}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{fffd}', 'ž', 'Ÿ',
+];
+
+pub fn decode_latin1(content: &[u8]) -> String {
+    content.iter().map(|b| *b as char).collect()
+}
+
+pub fn decode_cp1252(content: &[u8]) -> String {
+    content.iter().map(
+        |b| match b {
+            0x80..=0x9f => CP1252_HIGH[(*b - 0x80) as usize],
+            _ => *b as char,
         }
-    }
+    ).collect()
+}
 
-    let path = if let Some(p) = get_path_by_uid(file.uid) {
-        p
-    } else {
-        return None;
-    };
+// the text and the encoding that's actually used
+// `FileEncoding::Auto` returns `None` if the content looks binary
+pub fn decode_text(content: &[u8], encoding: FileEncoding) -> Option<(String, FileEncoding)> {
+    match encoding {
+        FileEncoding::Utf8 => try_extract_utf8_text(content).map(|s| (s, FileEncoding::Utf8)),
+        FileEncoding::Latin1 => Some((decode_latin1(content), FileEncoding::Latin1)),
+        FileEncoding::Cp1252 => Some((decode_cp1252(content), FileEncoding::Cp1252)),
+        FileEncoding::Auto => if let Some(s) = try_extract_utf8_text(content) {
+            Some((s, FileEncoding::Utf8))
+        }
 
-    let image = if let Ok(img) = ImageReader::open(path) {
-        img
-    } else {
-        return None;
-    };
+        // binary files usually have bytes in this range, but texts don't
+        else if content.iter().any(|b| *b <= 0x08) {
+            None
+        }
 
-    let image = if let Ok(reader) = image.with_guessed_format() {
-        reader
-    } else {
-        return None;
+        // 0x80..=0x9f are control characters in latin-1, but printable in cp1252
+        else if content.iter().any(|b| (0x80..=0x9f).contains(b)) {
+            Some((decode_cp1252(content), FileEncoding::Cp1252))
+        }
+
+        else {
+            Some((decode_latin1(content), FileEncoding::Latin1))
+        },
+    }
+}
+
+pub fn try_read_image(file: &File) -> Option<Arc<CachedImage>> {
+    if let Some(img) = get_image_from_cache(file.uid) {
+        return Some(img);
+    }
+
+    let image = match read_archive_member(file.uid) {
+        // members of an archive are not in the real file system
+        Some(Ok(bytes)) => match ImageReader::new(Cursor::new(bytes)).with_guessed_format() {
+            Ok(reader) => reader.decode().ok(),
+            Err(_) => None,
+        },
+        Some(Err(_)) => None,
+        None => {
+            let path = if let Some(p) = try_get_path_by_uid(file.uid) {
+                p
+            } else {
+                return None;
+            };
+
+            let image = if let Ok(img) = ImageReader::open(path) {
+                img
+            } else {
+                return None;
+            };
+
+            let image = if let Ok(reader) = image.with_guessed_format() {
+                reader
+            } else {
+                return None;
+            };
+
+            image.decode().ok()
+        },
     };
 
-    if let Ok(image) = image.decode() {
+    if let Some(image) = image {
         let decoded_image = image.to_rgb8();
 
         // registers the image to the cache
         // if it's already registered, it does nothing
         register_image_to_cache(&decoded_image, file.uid);
-        let cached_img = get_image_from_cache(file.uid);
 
-        Some(cached_img)
+        get_image_from_cache(file.uid)
     } else {
         None
     }
@@ -206,26 +509,24 @@ impl CachedImage {
     }
 }
 
-// It can store up to 8 images
-static mut IMAGE_CACHE: [(Uid, CachedImage); 8] = [
-    (Uid::DUMMY, CachedImage { w: 0, h: 0, data: Vec::new() }),
-    (Uid::DUMMY, CachedImage { w: 0, h: 0, data: Vec::new() }),
-    (Uid::DUMMY, CachedImage { w: 0, h: 0, data: Vec::new() }),
-    (Uid::DUMMY, CachedImage { w: 0, h: 0, data: Vec::new() }),
-    (Uid::DUMMY, CachedImage { w: 0, h: 0, data: Vec::new() }),
-    (Uid::DUMMY, CachedImage { w: 0, h: 0, data: Vec::new() }),
-    (Uid::DUMMY, CachedImage { w: 0, h: 0, data: Vec::new() }),
-    (Uid::DUMMY, CachedImage { w: 0, h: 0, data: Vec::new() }),
-];
+const MAX_IMAGE_CACHE_SIZE: usize = 8;
 
-// new image goes to here
-static mut IMAGE_CACHE_CURSOR: usize = 0;
+lazy_static! {
+    // (uid, image, last access)
+    // when it's full, the least recently accessed image is evicted
+    static ref IMAGE_CACHE: Mutex<Vec<(Uid, Arc<CachedImage>, u64)>> = Mutex::new(Vec::with_capacity(MAX_IMAGE_CACHE_SIZE));
+}
+
+// the last access of an image is the value of this clock at that moment
+static IMAGE_CACHE_CLOCK: AtomicU64 = AtomicU64::new(1);
+
+fn tick_image_cache_clock() -> u64 {
+    IMAGE_CACHE_CLOCK.fetch_add(1, Ordering::Relaxed)
+}
 
 fn register_image_to_cache(img: &RgbImage, uid: Uid) {
-    for (uid_, _) in unsafe { IMAGE_CACHE.iter() } {
-        if *uid_ == uid {
-            return;
-        }
+    if IMAGE_CACHE.lock().unwrap().iter().any(|(uid_, _, _)| *uid_ == uid) {
+        return;
     }
 
     let mut buffer = Vec::with_capacity(1 << 18);
@@ -242,35 +543,57 @@ fn register_image_to_cache(img: &RgbImage, uid: Uid) {
         }
     }
 
-    unsafe {
-        IMAGE_CACHE[IMAGE_CACHE_CURSOR] = (
-            uid,
-            CachedImage {
-                w: real_w as usize,
-                h: real_h as usize,
-                data: buffer,
-            },
-        );
-        IMAGE_CACHE_CURSOR = (IMAGE_CACHE_CURSOR + 1) & 7;
+    let entry = (
+        uid,
+        Arc::new(CachedImage {
+            w: real_w as usize,
+            h: real_h as usize,
+            data: buffer,
+        }),
+        tick_image_cache_clock(),
+    );
+    let mut cache = IMAGE_CACHE.lock().unwrap();
+
+    if cache.len() < MAX_IMAGE_CACHE_SIZE {
+        cache.push(entry);
+    }
+
+    else {
+        let (lru_index, _) = cache.iter().enumerate().min_by_key(
+            |(_, (_, _, last_access))| *last_access
+        ).unwrap();
+
+        cache[lru_index] = entry;
     }
 }
 
-// It panics if the image is not in the cache
-fn get_image_from_cache<'a>(uid: Uid) -> &'a CachedImage {
-    for (uid_, img) in unsafe { IMAGE_CACHE.iter() } {
+// it updates the last access of the image
+fn get_image_from_cache(uid: Uid) -> Option<Arc<CachedImage>> {
+    for (uid_, img, last_access) in IMAGE_CACHE.lock().unwrap().iter_mut() {
         if *uid_ == uid {
-            return img;
+            *last_access = tick_image_cache_clock();
+            return Some(img.clone());
         }
     }
 
-    panic!();
+    None
 }
 
+// `<1 µs`, `123 µs`, `4.567 ms`, `0.123 seconds`, `12 seconds` or `1m 23s`
 pub fn format_duration(duration: Duration) -> String {
     let secs = duration.as_secs();
+    let micros = duration.as_micros();
 
-    if secs == 0 {
-        format!("{} µs", duration.subsec_micros())
+    if micros == 0 {
+        String::from("<1 µs")
+    }
+
+    else if micros < 1000 {
+        format!("{micros} µs")
+    }
+
+    else if micros < 10_000 {
+        format!("{}.{:03} ms", micros / 1000, micros % 1000)
     }
 
     else if secs < 10 {
@@ -279,9 +602,38 @@ pub fn format_duration(duration: Duration) -> String {
         format!("{secs}.{millis:03} seconds")
     }
 
-    else {
+    else if secs < 60 {
         format!("{secs} seconds")
     }
+
+    else {
+        format!("{}m {}s", secs / 60, secs % 60)
+    }
+}
+
+// dark themes assume a dark background, and light themes assume a light background
+// colors that are too close to the background are replaced with visible ones
+pub fn adjust_color_for_background(c: SyColor, theme: &str) -> Color {
+    let is_light_theme = matches!(theme, "Solarized (light)" | "InspiredGitHub");
+
+    if is_light_theme {
+        // not visible on a light background
+        if c.r > 190 && c.g > 190 && c.b > 190 {
+            colors::DARK_GRAY
+        }
+
+        else if c.r < 60 && c.g < 60 && c.b < 60 {
+            colors::BLACK
+        }
+
+        else {
+            Color::TrueColor { r: c.r, g: c.g, b: c.b }
+        }
+    }
+
+    else {
+        convert_ocean_dark_color(c)
+    }
 }
 
 pub fn convert_ocean_dark_color(c: SyColor) -> Color {
@@ -301,17 +653,71 @@ pub fn convert_ocean_dark_color(c: SyColor) -> Color {
 }
 
 // TODO: better implementation
+// it splits at the middle grapheme cluster, so that it doesn't break emojis or combining characters
 pub fn split_long_str(s: String) -> Vec<String> {
     if s.len() < 60 {
         vec![s]
     }
 
     else {
-        let char_count = s.chars().count();
+        let graphemes = s.graphemes(true).collect::<Vec<&str>>();
+        let mid = graphemes.len() >> 1;
 
         vec![
-            vec![s.chars().collect::<Vec<char>>()[..(char_count >> 1)].iter().collect::<String>()],
-            vec![s.chars().collect::<Vec<char>>()[(char_count >> 1)..].iter().collect::<String>()],
-        ].concat()
+            graphemes[..mid].concat(),
+            graphemes[mid..].concat(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prettify_time_in_the_future() {
+        let now = SystemTime::now();
+        let an_hour_later = now + Duration::from_secs(3600);
+
+        assert_eq!(prettify_time(&now, an_hour_later), "in the future");
+        assert_eq!(colorize_time(&now, an_hour_later), colors::BLUE);
+        assert_eq!(prettify_time(&now, now), "just now   ");
+    }
+
+    #[test]
+    fn split_long_str_keeps_graphemes() {
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+
+        // the middle char is inside the family emoji
+        let s = format!("Hello {}{family}{} World", "a".repeat(24), "b".repeat(24));
+        let pieces = split_long_str(s.clone());
+
+        assert_eq!(pieces.len(), 2);
+        assert_eq!(pieces.concat(), s);
+        assert!(pieces.iter().any(|piece| piece.contains(family)));
+
+        // short strings are not split
+        assert_eq!(split_long_str(format!("Hello {family} World")), vec![format!("Hello {family} World")]);
+    }
+
+    #[test]
+    fn format_duration_boundaries() {
+        for (duration, expected) in [
+            (Duration::ZERO, "<1 µs"),
+            (Duration::from_nanos(999), "<1 µs"),
+            (Duration::from_micros(1), "1 µs"),
+            (Duration::from_micros(999), "999 µs"),
+            (Duration::from_millis(1), "1.000 ms"),
+            (Duration::from_micros(9_999), "9.999 ms"),
+            (Duration::from_millis(10), "0.010 seconds"),
+            (Duration::from_millis(9_999), "9.999 seconds"),
+            (Duration::from_secs(10), "10 seconds"),
+            (Duration::from_millis(59_999), "59 seconds"),
+            (Duration::from_secs(60), "1m 0s"),
+            (Duration::from_secs(83), "1m 23s"),
+            (Duration::from_secs(3600), "60m 0s"),
+        ] {
+            assert_eq!(format_duration(duration), expected);
+        }
     }
 }
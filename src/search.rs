@@ -0,0 +1,162 @@
+use crate::archive::read_archive_member;
+use crate::print::try_extract_utf8_text;
+use crate::uid::Uid;
+use crate::utils::{get_file_by_uid, try_get_path_by_uid};
+use lazy_static::lazy_static;
+use memchr::memmem::Finder;
+use regex::Regex;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::sync::Mutex;
+
+// `grep_dir` doesn't read files larger than this
+const MAX_GREP_FILE_SIZE: u64 = 16 * 1024 * 1024;
+
+// `find_byte_sequence` reads a file in chunks of this size
+const BYTE_SEARCH_CHUNK_SIZE: usize = 1 << 20;
+
+lazy_static! {
+    // uid -> number of matched lines, of the last `;G`
+    static ref MATCH_COUNTS: Mutex<HashMap<Uid, usize>> = Mutex::new(HashMap::new());
+}
+
+// (uid, number of matched lines) of the text files under `uid` that match `pattern`
+// it's like `grep -rc`, but it doesn't include files without any match
+pub fn grep_dir(uid: Uid, pattern: &Regex, show_hidden: bool) -> Vec<(Uid, usize)> {
+    let dir = match get_file_by_uid(uid) {
+        Some(dir) if dir.is_dir() => dir,
+        _ => {
+            return vec![];
+        },
+    };
+    let mut result = vec![];
+
+    for file in dir.get_descendants(show_hidden, None) {
+        if !file.is_file() || file.size > MAX_GREP_FILE_SIZE {
+            continue;
+        }
+
+        match find_matched_lines(file.uid, pattern) {
+            Some(lines) if !lines.is_empty() => {
+                result.push((file.uid, lines.len()));
+            },
+            _ => {},
+        }
+    }
+
+    result
+}
+
+// 0-based indexes of the lines that match `pattern`
+// it's `None` if the file cannot be read or is not a utf-8 text
+pub fn find_matched_lines(uid: Uid, pattern: &Regex) -> Option<Vec<usize>> {
+    let path = try_get_path_by_uid(uid)?;
+    let content = fs::read(path).ok()?;
+    let text = try_extract_utf8_text(&content)?;
+
+    Some(
+        text.lines().enumerate().filter(
+            |(_, line)| pattern.is_match(line)
+        ).map(
+            |(index, _)| index
+        ).collect()
+    )
+}
+
+// `0xAB CD EF` -> `[0xab, 0xcd, 0xef]`, a token may have multiple bytes (`0xABCD EF`)
+// anything else is searched as it is: `ELF` -> `[0x45, 0x4c, 0x46]`
+// it's `None` if the pattern is empty, or the hex bytes are invalid
+pub fn parse_byte_pattern(pattern: &str) -> Option<Vec<u8>> {
+    let result = match pattern.strip_prefix("0x").or_else(|| pattern.strip_prefix("0X")) {
+        Some(hex) => {
+            let mut result = vec![];
+
+            for token in hex.split_whitespace() {
+                let token = token.strip_prefix("0x").or_else(|| token.strip_prefix("0X")).unwrap_or(token);
+
+                if token.len() % 2 == 1 || !token.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return None;
+                }
+
+                for i in (0..token.len()).step_by(2) {
+                    result.push(u8::from_str_radix(&token[i..(i + 2)], 16).ok()?);
+                }
+            }
+
+            result
+        },
+        None => pattern.as_bytes().to_vec(),
+    };
+
+    if result.is_empty() {
+        None
+    }
+
+    else {
+        Some(result)
+    }
+}
+
+// byte offsets of the (non-overlapping) occurrences of `needle` in the file
+// it reads the file in chunks, so it works with large files
+// it's `None` if the file cannot be read
+pub fn find_byte_sequence(uid: Uid, needle: &[u8]) -> Option<Vec<usize>> {
+    if needle.is_empty() {
+        return Some(vec![]);
+    }
+
+    let finder = Finder::new(needle);
+
+    if let Some(bytes) = read_archive_member(uid) {
+        return Some(finder.find_iter(&bytes.ok()?).collect());
+    }
+
+    let path = try_get_path_by_uid(uid)?;
+    let mut file = fs::File::open(path).ok()?;
+    let mut result = vec![];
+
+    // the last `needle.len() - 1` bytes of the previous chunk are kept,
+    // so that the matches across the chunks are not missed
+    let mut window = vec![];
+    let mut window_offset = 0;
+    let mut chunk = vec![0; BYTE_SEARCH_CHUNK_SIZE];
+
+    loop {
+        let bytes_read = file.read(&mut chunk).ok()?;
+
+        if bytes_read == 0 {
+            break;
+        }
+
+        window.extend_from_slice(&chunk[..bytes_read]);
+
+        for index in finder.find_iter(&window) {
+            let offset = window_offset + index;
+
+            if result.last().map(|last| offset >= last + needle.len()).unwrap_or(true) {
+                result.push(offset);
+            }
+        }
+
+        let keep = (needle.len() - 1).min(window.len());
+        window_offset += window.len() - keep;
+        window.drain(..(window.len() - keep));
+    }
+
+    Some(result)
+}
+
+pub fn set_grep_results(results: &[(Uid, usize)]) {
+    let mut match_counts = MATCH_COUNTS.lock().unwrap();
+    match_counts.clear();
+
+    for (uid, count) in results.iter() {
+        match_counts.insert(*uid, *count);
+    }
+}
+
+// it's `None` if `uid` is not in the results of the last `;G`
+pub fn get_match_count(uid: Uid) -> Option<usize> {
+    MATCH_COUNTS.lock().unwrap().get(&uid).copied()
+}
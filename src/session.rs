@@ -0,0 +1,90 @@
+use crate::utils::get_data_path;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Mutex;
+
+// States that survive across launches.
+// It's persisted to `~/.local/share/file_query/session`.
+struct Session {
+    // path -> size, loaded at launch
+    // it's never updated, so that `ColumnKind::Delta` always compares with the last session
+    previous_sizes: HashMap<String, u64>,
+
+    // path -> size, of the files that the user has seen in this session
+    curr_sizes: HashMap<String, u64>,
+
+    has_changed: bool,
+}
+
+lazy_static! {
+    static ref SESSION: Mutex<Session> = Mutex::new(Session::load());
+}
+
+impl Session {
+    // It returns an empty session if there's no session file.
+    fn load() -> Self {
+        let mut previous_sizes = HashMap::new();
+
+        if let Some(Ok(s)) = get_data_path("session").map(fs::read_to_string) {
+            for line in s.lines() {
+                // a path may contain tabs, but the size cannot
+                if let Some((path, size)) = line.rsplit_once('\t') {
+                    if let Ok(size) = size.parse::<u64>() {
+                        previous_sizes.insert(path.to_string(), size);
+                    }
+                }
+            }
+        }
+
+        Session {
+            previous_sizes,
+            curr_sizes: HashMap::new(),
+            has_changed: false,
+        }
+    }
+}
+
+pub fn record_size(path: &str, size: u64) {
+    let mut session = SESSION.lock().unwrap();
+
+    if session.curr_sizes.get(path) != Some(&size) {
+        session.curr_sizes.insert(path.to_string(), size);
+        session.has_changed = true;
+    }
+}
+
+// size of the file when the last session ended
+pub fn get_previous_size(path: &str) -> Option<u64> {
+    SESSION.lock().unwrap().previous_sizes.get(path).copied()
+}
+
+/// It does nothing if nothing has changed since the last save.
+/// It doesn't bother the user if it fails to save the session.
+pub fn save_session() {
+    let mut session = SESSION.lock().unwrap();
+
+    if !session.has_changed {
+        return;
+    }
+
+    if let Some(path) = get_data_path("session") {
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+
+        // the files that the user hasn't seen in this session keep their old sizes
+        let mut sizes = session.previous_sizes.clone();
+        sizes.extend(session.curr_sizes.iter().map(|(path, size)| (path.clone(), *size)));
+
+        let lines = sizes.iter().filter(
+            |(path, _)| !path.contains('\n')
+        ).map(
+            |(path, size)| format!("{path}\t{size}")
+        ).collect::<Vec<_>>();
+
+        if fs::write(path, lines.join("\n")).is_ok() {
+            session.has_changed = false;
+        }
+    }
+}
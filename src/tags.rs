@@ -0,0 +1,125 @@
+use colored::Color;
+use crate::colors;
+use crate::utils::get_config_path;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+// Color labels of files, like the ones of macOS Finder.
+// They're persisted to `~/.config/file_query/tags.toml`, as `"path" = "color"`.
+lazy_static! {
+    static ref TAGS: Mutex<HashMap<String, TagColor>> = Mutex::new(load_tags());
+}
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum TagColor {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+}
+
+impl TagColor {
+    pub fn to_color(&self) -> Color {
+        match self {
+            TagColor::Red => colors::RED,
+            TagColor::Orange => colors::ORANGE,
+            TagColor::Yellow => colors::YELLOW,
+            TagColor::Green => colors::GREEN,
+            TagColor::Blue => colors::BLUE,
+            TagColor::Purple => colors::PURPLE,
+        }
+    }
+}
+
+impl FromStr for TagColor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "red" => Ok(TagColor::Red),
+            "orange" => Ok(TagColor::Orange),
+            "yellow" => Ok(TagColor::Yellow),
+            "green" => Ok(TagColor::Green),
+            "blue" => Ok(TagColor::Blue),
+            "purple" => Ok(TagColor::Purple),
+            _ => Err(format!("unknown color {s:?}, available colors: red, orange, yellow, green, blue, purple")),
+        }
+    }
+}
+
+impl fmt::Display for TagColor {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(
+            fmt, "{}",
+            match self {
+                TagColor::Red => "red",
+                TagColor::Orange => "orange",
+                TagColor::Yellow => "yellow",
+                TagColor::Green => "green",
+                TagColor::Blue => "blue",
+                TagColor::Purple => "purple",
+            }
+        )
+    }
+}
+
+// It returns an empty map if there's no tags file.
+// Unknown colors are ignored.
+fn load_tags() -> HashMap<String, TagColor> {
+    let mut result = HashMap::new();
+
+    if let Some(Ok(s)) = get_config_path("tags.toml").map(fs::read_to_string) {
+        if let Ok(tags) = toml::from_str::<HashMap<String, String>>(&s) {
+            for (path, color) in tags.into_iter() {
+                if let Ok(color) = color.parse::<TagColor>() {
+                    result.insert(path, color);
+                }
+            }
+        }
+    }
+
+    result
+}
+
+pub fn get_tag(path: &str) -> Option<TagColor> {
+    TAGS.lock().unwrap().get(path).copied()
+}
+
+// `None` removes the tag
+// it saves the tags file immediately
+pub fn set_tag(path: &str, color: Option<TagColor>) -> Result<(), String> {
+    let mut tags = TAGS.lock().unwrap();
+
+    match color {
+        Some(color) => {
+            tags.insert(path.to_string(), color);
+        },
+        None => {
+            tags.remove(path);
+        },
+    }
+
+    let tags_path = match get_config_path("tags.toml") {
+        Some(path) => path,
+        None => {
+            return Err(String::from("$HOME is not available"));
+        },
+    };
+
+    if let Some(parent) = tags_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    let tags = tags.iter().map(
+        |(path, color)| (path.clone(), color.to_string())
+    ).collect::<HashMap<_, _>>();
+    let s = toml::to_string(&tags).map_err(|e| e.to_string())?;
+
+    fs::write(tags_path, s).map_err(|e| e.to_string())
+}
@@ -0,0 +1,49 @@
+use crate::{File, FILES, PATHS, Uid};
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+
+// helpers of the unit tests
+
+lazy_static! {
+    // `FILES` and `PATHS` are not thread-safe, but cargo runs the tests in parallel
+    static ref GLOBALS_LOCK: Mutex<()> = Mutex::new(());
+}
+
+// a test that touches `FILES` or `PATHS` has to hold this until it ends
+// it initializes them if they're not yet
+pub fn lock_globals() -> MutexGuard<'static, ()> {
+    // a failed test poisons the lock, but the other tests are still fine
+    let guard = GLOBALS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+    unsafe {
+        if FILES.is_null() {
+            FILES = Box::leak(Box::new(HashMap::new())) as *mut HashMap<_, _>;
+            PATHS = Box::leak(Box::new(HashMap::new())) as *mut HashMap<_, _>;
+        }
+    }
+
+    guard
+}
+
+// it creates an empty dir in the temp dir, with `children` in it, and registers the dir
+// a child that ends with `/` is a dir, and the others are empty files
+pub fn make_temp_dir(name: &str, children: &[&str]) -> (PathBuf, Uid) {
+    let dir = std::env::temp_dir().join(format!("hfile-test-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    for child in children.iter() {
+        match child.strip_suffix('/') {
+            Some(child) => fs::create_dir_all(dir.join(child)).unwrap(),
+            None => fs::write(dir.join(child), b"").unwrap(),
+        }
+    }
+
+    let dir = fs::canonicalize(&dir).unwrap();
+    let uid = File::new_from_path_buf(dir.clone(), Some(Uid::from_path(&dir.to_string_lossy())), None);
+
+    (dir, uid)
+}
@@ -1,10 +1,19 @@
+use crate::utils::{is_uid_registered, try_get_path_by_uid};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use std::fmt;
+
 // has nothing to do with inode
 #[derive(Clone, Copy, Eq, Hash, PartialEq)]
 pub struct Uid(u128);
 
 impl Uid {
-    pub const BASE: Self = Uid(0);
+    // the dir where file_query was started (`current_dir()`), `~~` goes here
+    pub const LAUNCH: Self = Uid(0);
     pub const ROOT: Self = Uid(1);
+
+    // `$HOME`, `~` goes here, and it's registered only if the variable is set
+    pub const HOME: Self = Uid(2);
     pub const DUMMY: Self = Uid(u128::MAX);
 }
 
@@ -13,6 +22,29 @@ impl Uid {
         Uid(rand::random::<u128>() & !(0xf << 124))
     }
 
+    // the same path always gets the same uid, even across sessions
+    // it's the first 124 bits of the sha256 hash of the path
+    pub fn from_path(path: &str) -> Self {
+        let hash = Sha256::digest(path.as_bytes());
+        let mut bytes = [0; 16];
+        bytes.copy_from_slice(&hash[..16]);
+
+        Uid(u128::from_be_bytes(bytes) & !(0xf << 124))
+    }
+
+    // it's `from_path`, unless the path is registered with a fixed uid (`LAUNCH`, `HOME` or `ROOT`)
+    // `File::new_from_XXX` uses it, so that reading the parent of `LAUNCH` doesn't register the dir twice
+    // it's called for every dir entry, so it doesn't search all the registered paths like `get_uid_by_path`
+    pub fn from_path_cached(path: &str) -> Self {
+        for uid in [Uid::LAUNCH, Uid::HOME, Uid::ROOT] {
+            if is_uid_registered(uid) && try_get_path_by_uid(uid) == Some(path) {
+                return uid;
+            }
+        }
+
+        Uid::from_path(path)
+    }
+
     pub fn error() -> Self {
         Uid(rand::random::<u128>() & !(0xf << 124) | (0x1 << 124))
     }
@@ -44,7 +76,7 @@ impl Uid {
             }
 
             else {
-                unreachable!()
+                format!("Uid({:#x})", self.0)
             }
         }
 
@@ -53,3 +85,95 @@ impl Uid {
         }
     }
 }
+
+// a short form for alerts and error messages
+// `#<last 6 hex digits>` for normal files, `!<last 4 hex digits>` for errors,
+// `T<n>` for truncated rows and `M<last 4 hex digits>` for messages
+// the other values (e.g. `Uid::DUMMY` or a broken session file) are `?<full hex>`
+// use `debug_info` if you need the full value
+impl fmt::Display for Uid {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let value = self.0 & !(0xf << 124);
+
+        match self.0 >> 124 {
+            0x0 => write!(fmt, "#{:06x}", value & 0xff_ffff),
+            0x1 => write!(fmt, "!{:04x}", value & 0xffff),
+            0x2 => write!(fmt, "T{value}"),
+            0x3 => write!(fmt, "M{:04x}", value & 0xffff),
+            _ => write!(fmt, "?{:x}", self.0),
+        }
+    }
+}
+
+// it's serialized to a hex string, like "0x0123456789abcdef0123456789abcdef"
+impl Serialize for Uid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("0x{:032x}", self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for Uid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+
+        match s.strip_prefix("0x") {
+            Some(hex) if hex.len() == 32 => u128::from_str_radix(hex, 16).map(Uid).map_err(de::Error::custom),
+            _ => Err(de::Error::custom(format!("invalid uid: {s:?}"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{File, get_file_by_uid};
+    use crate::testing::{lock_globals, make_temp_dir};
+
+    fn round_trip(uid: Uid) -> Uid {
+        toml::Value::try_from(uid).unwrap().try_into::<Uid>().unwrap()
+    }
+
+    #[test]
+    fn from_path_cached_reuses_fixed_uids() {
+        let _globals = lock_globals();
+        let (path, dir) = make_temp_dir("cached", &["home/", "other/"]);
+        let home = path.join("home");
+        File::new_from_path_buf(home.clone(), Some(Uid::HOME), None);
+
+        assert!(Uid::from_path_cached(&home.to_string_lossy()) == Uid::HOME);
+        assert!(Uid::from_path_cached(&path.to_string_lossy()) == dir);
+
+        let children = get_file_by_uid(dir).unwrap().get_children(true).iter().map(|f| f.uid).collect::<Vec<_>>();
+        assert_eq!(children.len(), 2);
+        assert!(children.contains(&Uid::HOME));
+        assert!(children.contains(&Uid::from_path(&path.join("other").to_string_lossy())));
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        for uid in [Uid::LAUNCH, Uid::ROOT, Uid::HOME, Uid::from_path("/home/user"), Uid::normal_file(), Uid::error()] {
+            assert!(round_trip(uid) == uid);
+        }
+    }
+
+    #[test]
+    fn serialized_as_hex_string() {
+        assert_eq!(
+            toml::Value::try_from(Uid::HOME).unwrap(),
+            toml::Value::String(String::from("0x00000000000000000000000000000002")),
+        );
+        assert!(toml::Value::String(String::from("0x2")).try_into::<Uid>().is_err());
+        assert!(toml::Value::String(String::from("2")).try_into::<Uid>().is_err());
+    }
+
+    #[test]
+    fn display_does_not_panic() {
+        assert_eq!(Uid::HOME.to_string(), "#000002");
+        assert_eq!(Uid::message_for_truncated_rows(3).to_string(), "T3");
+        assert_eq!(Uid::DUMMY.to_string(), format!("?{:x}", u128::MAX));
+
+        let uid = toml::Value::String(String::from("0x40000000000000000000000000000001")).try_into::<Uid>().unwrap();
+        assert_eq!(uid.to_string(), "?40000000000000000000000000000001");
+        assert_eq!(uid.debug_info(), "Uid(0x40000000000000000000000000000001)");
+    }
+}
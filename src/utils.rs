@@ -1,7 +1,24 @@
-use crate::{File, FILES, Path, PATHS, Uid};
+use crate::{File, FileType, FILES, Path, PATHS, Uid};
 use crate::print::ColumnKind;
+use crate::search::get_match_count;
+use crate::session::get_previous_size;
+use crate::tags::get_tag;
+use filetime::FileTime;
+use lazy_static::lazy_static;
+use std::collections::HashSet;
+use std::fs;
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+lazy_static! {
+    // (path, message) of every error that `File::from_io_error` and `File::from_error_msg` have seen
+    static ref ERROR_LOG: Mutex<Vec<(String, String)>> = Mutex::new(vec![]);
+
+    // see `intern_xattr_key`
+    static ref XATTR_KEYS: Mutex<HashSet<&'static str>> = Mutex::new(HashSet::new());
+}
 
 pub fn get_file_by_uid<'a>(uid: Uid) -> Option<&'a mut File> {
     let files = unsafe { FILES.as_mut().unwrap() };
@@ -9,6 +26,13 @@ pub fn get_file_by_uid<'a>(uid: Uid) -> Option<&'a mut File> {
     files.get_mut(&uid)
 }
 
+// see `debug_assert_uid_valid!`
+pub fn is_uid_registered(uid: Uid) -> bool {
+    let files = unsafe { FILES.as_ref().unwrap() };
+
+    files.contains_key(&uid)
+}
+
 // It returns `Some` if `uid` is valid.
 pub fn get_path_by_uid<'a>(uid: Uid) -> Option<&'a Path> {
     let paths = unsafe { PATHS.as_mut().unwrap() };
@@ -35,10 +59,86 @@ pub fn get_path_by_uid<'a>(uid: Uid) -> Option<&'a Path> {
     }
 }
 
+// it removes `uid` and its descendants from `FILES` and `PATHS`, and from the children of every dir
+// the ancestors' `recursive_size` are invalidated
+pub fn unregister_file(uid: Uid) {
+    let files = unsafe { FILES.as_mut().unwrap() };
+    let paths = unsafe { PATHS.as_mut().unwrap() };
+
+    let file = match files.remove(&uid) {
+        Some(file) => file,
+        None => {
+            return;
+        },
+    };
+    paths.remove(&uid);
+
+    // not only `file.parent`: a dir can have more than one instance (e.g. `Uid::LAUNCH` and
+    // the one its parent has registered), and all of them list the same children
+    for f in files.values_mut() {
+        if let Some(children) = &mut f.children {
+            children.retain(|child| *child != uid);
+        }
+    }
+
+    let mut ancestor = file.parent;
+
+    while let Some(uid) = ancestor {
+        match files.get_mut(&uid) {
+            Some(f) => {
+                f.recursive_size = None;
+                ancestor = f.parent;
+            },
+            None => {
+                break;
+            },
+        }
+    }
+
+    let mut descendants = file.children.unwrap_or_default();
+
+    while let Some(uid) = descendants.pop() {
+        // the main loop relies on these
+        if uid == Uid::ROOT || uid == Uid::LAUNCH || uid == Uid::HOME {
+            continue;
+        }
+
+        if let Some(f) = files.remove(&uid) {
+            paths.remove(&uid);
+            descendants.extend(f.children.unwrap_or_default());
+        }
+    }
+}
+
+// the reverse of `get_path_by_uid`
+// it's `None` if no registered file has the path
+pub fn get_uid_by_path(path: &str) -> Option<Uid> {
+    // most files get their uid from their path
+    let uid = Uid::from_path(path);
+
+    if is_uid_registered(uid) && try_get_path_by_uid(uid) == Some(path) {
+        return Some(uid);
+    }
+
+    // the others, like `Uid::LAUNCH`, have to be searched
+    let paths = unsafe { PATHS.as_ref().unwrap() };
+
+    paths.iter().find(
+        |(uid, p)| p.as_str() == path && is_uid_registered(**uid)
+    ).map(
+        |(uid, _)| *uid
+    )
+}
+
+// `Path` is an alias of `String`, so it's more convenient to use `&str` with `format!`
+pub fn try_get_path_by_uid<'a>(uid: Uid) -> Option<&'a str> {
+    get_path_by_uid(uid).map(|path| path.as_str())
+}
+
 fn get_path_by_file(file: &File) -> Option<String> {
     match file.parent {
         Some(parent) => {
-            let parent_path = get_path_by_uid(parent).unwrap();
+            let parent_path = try_get_path_by_uid(parent).unwrap();
             let mut parent_path = PathBuf::from_str(parent_path).unwrap();  // infallible
             let child_path = PathBuf::from_str(&file.name).unwrap();  // infallible
 
@@ -51,6 +151,288 @@ fn get_path_by_file(file: &File) -> Option<String> {
     }
 }
 
+// sum of the sizes of the files under `path`, without registering them to `FILES`
+// it's O(n) file system calls, and workers can call it because it doesn't touch `FILES`
+// it doesn't follow symlinks, like `File::get_recursive_size`
+// it sums the apparent sizes (`metadata.len()`), not the allocated blocks like `du`, because
+// `File::get_recursive_size_blocking` mixes it with `File::size` of the loaded children
+pub fn get_total_dir_size_fast(path: &str) -> u64 {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => {},
+        Ok(metadata) => {
+            return metadata.len();
+        },
+        Err(_) => {
+            return 0;
+        },
+    }
+
+    let mut result = 0;
+    let mut dirs = vec![PathBuf::from(path)];
+
+    while let Some(dir) = dirs.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => {
+                continue;
+            },
+        };
+
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            // `DirEntry::file_type` doesn't need another syscall on most platforms
+            match entry.file_type() {
+                Ok(file_type) if file_type.is_dir() => {
+                    dirs.push(entry.path());
+                },
+                Ok(_) => {
+                    result += entry.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+                },
+                Err(_) => {},
+            }
+        }
+    }
+
+    result
+}
+
+// ancestors of `uid` from `Uid::LAUNCH` (or the root dir, if `uid` is not inside `Uid::LAUNCH`), and `uid` itself
+// like `Q`, it registers the ancestors on the way
+pub fn get_breadcrumb(uid: Uid) -> Vec<Uid> {
+    let mut result = vec![uid];
+    let mut curr_uid = uid;
+
+    while curr_uid != Uid::LAUNCH && curr_uid != Uid::ROOT {
+        let curr_file = match get_file_by_uid(curr_uid) {
+            Some(file) => file,
+            None => {
+                break;
+            },
+        };
+
+        // the root of a windows drive doesn't have a parent
+        match try_get_path_by_uid(curr_uid).map(|path| std::path::Path::new(path).parent().is_some()) {
+            Some(true) => {},
+            _ => {
+                break;
+            },
+        }
+
+        curr_uid = curr_file.get_parent_uid();
+        result.push(curr_uid);
+    }
+
+    result.reverse();
+    result
+}
+
+// (target, whether the target exists)
+// it's `None` if `file` is not a symlink
+pub fn get_symlink_target(file: &File) -> Option<(String, bool)> {
+    if file.file_type != FileType::Symlink {
+        return None;
+    }
+
+    let path = try_get_path_by_uid(file.uid)?;
+    let target = fs::read_link(path).ok()?.display().to_string();
+
+    // `fs::metadata` follows the link, but `fs::symlink_metadata` doesn't
+    Some((target, fs::metadata(path).is_ok()))
+}
+
+// linux only allows unprivileged users to use the `user.` namespace, so `key` goes there unless it has a namespace
+fn get_xattr_name(key: &str) -> String {
+    if cfg!(target_os = "linux") && !key.contains('.') {
+        format!("user.{key}")
+    }
+
+    else {
+        key.to_string()
+    }
+}
+
+// `Ok(None)` if the file doesn't have the attribute
+// `Err` if the file system doesn't support xattrs
+#[cfg(unix)]
+pub fn get_xattr(path: &str, key: &str) -> Result<Option<String>, String> {
+    match xattr::get(path, get_xattr_name(key)) {
+        Ok(value) => Ok(value.map(|value| String::from_utf8_lossy(&value).to_string())),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[cfg(unix)]
+pub fn set_xattr(path: &str, key: &str, value: &str) -> Result<(), String> {
+    xattr::set(path, get_xattr_name(key), value.as_bytes()).map_err(|e| e.to_string())
+}
+
+#[cfg(unix)]
+pub fn remove_xattr(path: &str, key: &str) -> Result<(), String> {
+    xattr::remove(path, get_xattr_name(key)).map_err(|e| e.to_string())
+}
+
+#[cfg(not(unix))]
+const XATTR_UNSUPPORTED: &str = "xattrs are not supported on this platform";
+
+#[cfg(not(unix))]
+pub fn get_xattr(_path: &str, _key: &str) -> Result<Option<String>, String> {
+    Err(String::from(XATTR_UNSUPPORTED))
+}
+
+#[cfg(not(unix))]
+pub fn set_xattr(_path: &str, _key: &str, _value: &str) -> Result<(), String> {
+    Err(String::from(XATTR_UNSUPPORTED))
+}
+
+#[cfg(not(unix))]
+pub fn remove_xattr(_path: &str, _key: &str) -> Result<(), String> {
+    Err(String::from(XATTR_UNSUPPORTED))
+}
+
+// `ColumnKind::CustomTag` needs a `&'static str`
+// each key is leaked only once, no matter how many times `;x <key>` is run
+pub fn intern_xattr_key(key: &str) -> &'static str {
+    let mut keys = XATTR_KEYS.lock().unwrap();
+
+    match keys.get(key) {
+        Some(key) => key,
+        None => {
+            let key: &'static str = Box::leak(key.to_string().into_boxed_str());
+            keys.insert(key);
+            key
+        },
+    }
+}
+
+pub fn filetime_to_system_time(time: FileTime) -> SystemTime {
+    let nanos = Duration::from_nanos(time.nanoseconds() as u64);
+
+    if time.unix_seconds() >= 0 {
+        UNIX_EPOCH + Duration::from_secs(time.unix_seconds() as u64) + nanos
+    }
+
+    else {
+        UNIX_EPOCH - Duration::from_secs(time.unix_seconds().unsigned_abs()) + nanos
+    }
+}
+
+// size change since the last session
+// it's `None` if the file is not in the last session
+pub fn get_size_delta(file: &File) -> Option<i64> {
+    let path = try_get_path_by_uid(file.uid)?;
+    let previous_size = get_previous_size(path)?;
+
+    Some(file.size as i64 - previous_size as i64)
+}
+
+// both `/` and `\` are separators, on every platform
+// `a/b\c` -> `["a", "b", "c"]`
+pub fn split_path_input(input: &str) -> Vec<&str> {
+    input.split(['/', '\\']).collect()
+}
+
+// `C:` -> `C:\`, it's `None` if the drive doesn't exist
+#[cfg(windows)]
+fn get_drive_root_uid(segment: &str) -> Option<Uid> {
+    let chars = segment.chars().collect::<Vec<_>>();
+
+    if chars.len() != 2 || !chars[0].is_ascii_alphabetic() || chars[1] != ':' {
+        return None;
+    }
+
+    let path = format!("{segment}\\");
+
+    if !std::path::Path::new(&path).is_dir() {
+        return None;
+    }
+
+    match get_uid_by_path(&path) {
+        Some(uid) => Some(uid),
+        None => Some(File::new_from_dir_path(path.clone(), Some(Uid::from_path(&path)), None)),
+    }
+}
+
+// `.` stays at the current dir, and `..` goes to the parent dir
+// the other segments must match the name of a child exactly
+// on windows, a segment can be a drive letter, like `C:`
+// it's `None` if an intermediate segment is not a dir
+pub fn iterate_paths(start: Uid, paths: &[&str]) -> Option<Uid> {  // TODO: Result<Uid, Error>
+    #[cfg(windows)]
+    if let Some(drive) = paths.first().and_then(|segment| get_drive_root_uid(segment)) {
+        return iterate_paths(drive, &paths[1..]);
+    }
+
+    if paths.is_empty() {
+        Some(start)
+    }
+
+    else if paths[0] == "." {
+        iterate_paths(start, &paths[1..])
+    }
+
+    else if paths[0] == ".." {
+        match get_file_by_uid(start) {
+            Some(f) if f.has_parent() => iterate_paths(f.get_parent_uid(), &paths[1..]),
+            _ => None,
+        }
+    }
+
+    else {
+        match get_file_by_uid(start) {
+            Some(f) if f.is_dir() => {
+                for child in f.get_children(true) {
+                    if child.name == paths[0] {
+                        if paths.len() > 1 && !child.is_dir() {
+                            return None;
+                        }
+
+                        return iterate_paths(child.uid, &paths[1..]);
+                    }
+                }
+
+                None
+            },
+            _ => None,
+        }
+    }
+}
+
+// Each segment of `paths` is a case-insensitive prefix of a child's name.
+// If multiple children match a segment, it chooses the one with the shortest name,
+// and the alphabetically first one among the names of the same length.
+// `.`, `..` and drive letters work like `iterate_paths`.
+pub fn search_by_prefix(curr_file: Uid, paths: &[&str]) -> Option<Uid> {  // TODO: Result<Uid, Error>
+    #[cfg(windows)]
+    if let Some(drive) = paths.first().and_then(|segment| get_drive_root_uid(segment)) {
+        return search_by_prefix(drive, &paths[1..]);
+    }
+
+    if paths.is_empty() {
+        Some(curr_file)
+    }
+
+    else if paths[0] == "." || paths[0] == ".." {
+        let next = iterate_paths(curr_file, &paths[..1])?;
+        search_by_prefix(next, &paths[1..])
+    }
+
+    else {
+        let prefix = paths[0].to_lowercase();
+        let f = get_file_by_uid(curr_file)?;
+
+        if !f.is_dir() {
+            return None;
+        }
+
+        let child = f.get_children(true).into_iter().filter(
+            |child| !child.is_special_file() && child.name.to_lowercase().starts_with(&prefix)
+        ).min_by_key(
+            |child| (child.name.chars().count(), child.name.as_str())
+        )?;
+
+        search_by_prefix(child.uid, &paths[1..])
+    }
+}
+
 pub fn sort_files(files: &mut Vec<&File>, sort_by: ColumnKind, reverse: bool) {
     match sort_by {
         ColumnKind::Index => unreachable!(),
@@ -60,21 +442,338 @@ pub fn sort_files(files: &mut Vec<&File>, sort_by: ColumnKind, reverse: bool) {
         ColumnKind::Size => {
             files.sort_by_key(|file| file.size);
         },
+        // `get_recursive_size` is 0 until the workers finish, so it has to block
         ColumnKind::TotalSize => {
-            files.sort_by_key(|file| file.get_recursive_size());
+            files.sort_by_cached_key(|file| file.get_recursive_size_blocking());
         },
-        ColumnKind::Modified => {
+        ColumnKind::Modified
+        | ColumnKind::Age => {
             files.sort_by_key(|file| file.last_modified);
         },
+        // `None` is the oldest
+        ColumnKind::Birthtime => {
+            files.sort_by_key(|file| file.created_at);
+        },
+        // `None` is `UNIX_EPOCH`
+        ColumnKind::LastOpened => {
+            files.sort_by_key(|file| file.last_opened.unwrap_or(UNIX_EPOCH));
+        },
         ColumnKind::FileType => {
             files.sort_by_key(|file| file.file_type);
         },
         ColumnKind::FileExt => {
             files.sort_by_key(|file| file.file_ext.clone().unwrap_or(String::new()));
         },
+        ColumnKind::Checksum => {
+            files.sort_by_key(|file| file.get_checksum());
+        },
+        ColumnKind::RelativePath => {
+            files.sort_by_key(|file| try_get_path_by_uid(file.uid));
+        },
+        ColumnKind::Depth => {
+            files.sort_by_key(|file| file.get_depth());
+        },
+        ColumnKind::WordCount => {
+            files.sort_by_key(|file| file.wc);
+        },
+        // files come first
+        ColumnKind::EntryCount => {
+            files.sort_by_cached_key(|file| file.is_dir().then(|| file.get_children_num(true)));
+        },
+        ColumnKind::Preview => {
+            files.sort_by_cached_key(|file| file.get_preview());
+        },
+        ColumnKind::SymlinkTarget => {
+            files.sort_by_cached_key(|file| get_symlink_target(file));
+        },
+        ColumnKind::Permissions(_) => {
+            files.sort_by_key(|file| file.mode);
+        },
+        // untagged files come first
+        ColumnKind::Color => {
+            files.sort_by_key(|file| try_get_path_by_uid(file.uid).and_then(get_tag));
+        },
+        ColumnKind::NetworkMount => {
+            files.sort_by_key(|file| file.is_network_mount);
+        },
+        ColumnKind::CustomTag(key) => {
+            files.sort_by_cached_key(|file| try_get_path_by_uid(file.uid).and_then(|path| get_xattr(path, key).ok().flatten()));
+        },
+        ColumnKind::MatchCount => {
+            files.sort_by_key(|file| get_match_count(file.uid));
+        },
+        ColumnKind::Delta => {
+            files.sort_by_key(|file| get_size_delta(file));
+        },
+        ColumnKind::Hardness => {
+            // files that share an inode are next to each other
+            files.sort_by_key(|file| if file.nlink > 1 { file.inode } else { None });
+        },
     }
 
     if reverse {
         files.reverse();
     }
 }
+
+// (total bytes, available bytes) of the file system that `path` belongs to
+#[cfg(unix)]
+pub fn get_disk_usage(path: &str) -> Option<(u64, u64)> {
+    let stat = nix::sys::statvfs::statvfs(path).ok()?;
+    let fragment_size = stat.fragment_size() as u64;
+
+    Some((
+        stat.blocks() as u64 * fragment_size,
+        stat.blocks_available() as u64 * fragment_size,
+    ))
+}
+
+#[cfg(windows)]
+pub fn get_disk_usage(path: &str) -> Option<(u64, u64)> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    // it takes a null-terminated utf-16 string
+    let path = std::ffi::OsStr::new(path).encode_wide().chain(std::iter::once(0)).collect::<Vec<u16>>();
+    let (mut available, mut total, mut free) = (0, 0, 0);
+
+    // `available` respects the user's quota, like `f_bavail` of `statvfs`
+    let result = unsafe { GetDiskFreeSpaceExW(path.as_ptr(), &mut available, &mut total, &mut free) };
+
+    if result == 0 {
+        None
+    }
+
+    else {
+        Some((total, available))
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn get_disk_usage(_: &str) -> Option<(u64, u64)> {
+    None
+}
+
+// magic numbers of `statfs.f_type`: nfs, smb, smb2, cifs, afs and coda
+#[cfg(target_os = "linux")]
+const NETWORK_FILE_SYSTEMS: [u64; 6] = [0x6969, 0x517b, 0xfe534d42, 0xff534d42, 0x5346414f, 0x73757245];
+
+// reading a dir in a network file system might take very long
+#[cfg(target_os = "linux")]
+pub fn is_network_file_system(path: &str) -> bool {
+    match nix::sys::statfs::statfs(path) {
+        Ok(stat) => NETWORK_FILE_SYSTEMS.contains(&(stat.filesystem_type().0 as u64 & 0xffff_ffff)),
+        Err(_) => false,
+    }
+}
+
+// `statfs.f_fstypename` of network file systems
+#[cfg(target_os = "macos")]
+const NETWORK_FILE_SYSTEM_NAMES: [&str; 4] = ["nfs", "smbfs", "afpfs", "webdav"];
+
+#[cfg(target_os = "macos")]
+pub fn is_network_file_system(path: &str) -> bool {
+    match nix::sys::statfs::statfs(path) {
+        Ok(stat) => NETWORK_FILE_SYSTEM_NAMES.contains(&stat.filesystem_type_name()),
+        Err(_) => false,
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn is_network_file_system(_: &str) -> bool {
+    false
+}
+
+// `my file's.txt` -> `'my file'\''s.txt'`
+// it's safe to paste the result in sh, bash and zsh
+pub fn shell_quote(path: &str) -> String {
+    format!("'{}'", path.replace('\'', "'\\''"))
+}
+
+#[cfg(feature = "clipboard")]
+pub fn copy_to_clipboard(s: &str) -> Result<(), String> {
+    let mut clipboard = arboard::Clipboard::new().map_err(|e| e.to_string())?;
+
+    clipboard.set_text(s).map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "clipboard"))]
+pub fn copy_to_clipboard(_: &str) -> Result<(), String> {
+    Err(String::from("hfile is built without `clipboard` feature"))
+}
+
+// `~/.local/share/file_query/{name}`
+// hfile stores its states (history, session, ...) there
+pub fn get_data_path(name: &str) -> Option<PathBuf> {
+    match std::env::var("HOME") {
+        Ok(home) if !home.is_empty() => Some(
+            [home.as_str(), ".local", "share", "file_query", name].iter().collect()
+        ),
+        _ => None,
+    }
+}
+
+// `~/.config/file_query/{name}`
+// files that the user may edit (tags, ...) are there
+pub fn get_config_path(name: &str) -> Option<PathBuf> {
+    match std::env::var("HOME") {
+        Ok(home) if !home.is_empty() => Some(
+            [home.as_str(), ".config", "file_query", name].iter().collect()
+        ),
+        _ => None,
+    }
+}
+
+pub fn error_log_push(path: &str, message: &str) {
+    ERROR_LOG.lock().unwrap().push((path.to_string(), message.to_string()));
+}
+
+// (path, message), from the oldest to the newest
+pub fn get_error_log() -> Vec<(String, String)> {
+    ERROR_LOG.lock().unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{lock_globals, make_temp_dir};
+
+    #[test]
+    fn search_by_prefix_prefers_shorter_names() {
+        let _globals = lock_globals();
+        let (_, dir) = make_temp_dir("prefix-shorter", &["notes-2024", "notes", "Notebook/"]);
+
+        let found = search_by_prefix(dir, &["note"]).unwrap();
+        assert_eq!(get_file_by_uid(found).unwrap().name, "notes");
+
+        // case-insensitive
+        let found = search_by_prefix(dir, &["NOTEB"]).unwrap();
+        assert_eq!(get_file_by_uid(found).unwrap().name, "Notebook");
+
+        assert!(search_by_prefix(dir, &["x"]).is_none());
+    }
+
+    #[test]
+    fn search_by_prefix_breaks_ties_by_name() {
+        let _globals = lock_globals();
+        let (_, dir) = make_temp_dir("prefix-ties", &["abd", "abc", "abe"]);
+
+        // `read_dir` doesn't guarantee any order, so the result must not depend on it
+        let found = search_by_prefix(dir, &["ab"]).unwrap();
+        assert_eq!(get_file_by_uid(found).unwrap().name, "abc");
+    }
+
+    #[test]
+    fn search_by_prefix_goes_into_dirs() {
+        let _globals = lock_globals();
+        let (_, dir) = make_temp_dir("prefix-nested", &["src/", "src/main.rs", "src/lib.rs"]);
+
+        let found = search_by_prefix(dir, &["s", "m"]).unwrap();
+        assert_eq!(get_file_by_uid(found).unwrap().name, "main.rs");
+
+        let found = search_by_prefix(dir, &["s", ".."]).unwrap();
+        assert!(found == dir);
+    }
+
+    #[test]
+    fn iterate_paths_dot_and_dot_dot() {
+        let _globals = lock_globals();
+        let (_, dir) = make_temp_dir("iterate", &["a/", "a/f", "b/", "b/g"]);
+        let a = iterate_paths(dir, &["a"]).unwrap();
+        let b = iterate_paths(dir, &["b"]).unwrap();
+
+        // `..`
+        assert!(iterate_paths(a, &[".."]) == Some(dir));
+
+        // `../sibling`
+        assert!(iterate_paths(a, &["..", "b"]) == Some(b));
+        assert_eq!(get_file_by_uid(iterate_paths(a, &["..", "b", "g"]).unwrap()).unwrap().name, "g");
+
+        // `./file`
+        assert_eq!(get_file_by_uid(iterate_paths(a, &[".", "f"]).unwrap()).unwrap().name, "f");
+        assert!(iterate_paths(a, &["."]) == Some(a));
+    }
+
+    #[test]
+    fn iterate_paths_fails_on_files_and_missing_names() {
+        let _globals = lock_globals();
+        let (_, dir) = make_temp_dir("iterate-fail", &["a/", "a/f"]);
+
+        assert!(iterate_paths(dir, &["a", "f", "x"]).is_none());
+        assert!(iterate_paths(dir, &["a", "f", ".."]).is_none());
+        assert!(iterate_paths(dir, &["nope"]).is_none());
+
+        // names are case-sensitive, unlike `search_by_prefix`
+        assert!(iterate_paths(dir, &["A"]).is_none());
+    }
+
+    #[test]
+    fn split_path_input_accepts_both_separators() {
+        assert_eq!(split_path_input("a/b\\c"), vec!["a", "b", "c"]);
+        assert_eq!(split_path_input(r"Users\test"), vec!["Users", "test"]);
+        assert_eq!(split_path_input(r"..\sibling/file"), vec!["..", "sibling", "file"]);
+        assert_eq!(split_path_input(r"C:\Users\foo"), vec!["C:", "Users", "foo"]);
+        assert_eq!(split_path_input("name"), vec!["name"]);
+    }
+
+    #[test]
+    fn iterate_paths_with_backslashes() {
+        let _globals = lock_globals();
+        let (_, dir) = make_temp_dir("backslash", &["Users/", "Users/test/"]);
+
+        let found = iterate_paths(dir, &split_path_input(r"Users\test")).unwrap();
+        assert_eq!(get_file_by_uid(found).unwrap().name, "test");
+
+        let found = search_by_prefix(dir, &split_path_input(r"us\te")).unwrap();
+        assert_eq!(get_file_by_uid(found).unwrap().name, "test");
+    }
+
+    #[test]
+    fn unregister_file_from_every_instance() {
+        let _globals = lock_globals();
+        let (path, dir) = make_temp_dir("unregister", &["f", "g"]);
+
+        // like `Uid::LAUNCH` and the instance that its parent registers
+        let other = File::new_from_path_buf(path, Some(Uid::normal_file()), None);
+        let f = get_file_by_uid(dir).unwrap().get_children(true).into_iter().find(|f| f.name == "f").unwrap().uid;
+        assert_eq!(get_file_by_uid(other).unwrap().get_children(true).len(), 2);
+
+        unregister_file(f);
+
+        for uid in [dir, other] {
+            assert_eq!(get_file_by_uid(uid).unwrap().children.as_ref().unwrap().len(), 1);
+
+            let children = get_file_by_uid(uid).unwrap().get_children(true);
+            assert_eq!(children.len(), 1);
+            assert_eq!(children[0].name, "g");
+        }
+    }
+
+    #[test]
+    fn sort_by_total_size() {
+        let _globals = lock_globals();
+        let (path, dir) = make_temp_dir("total-size", &["big/", "small/", "empty/"]);
+        fs::write(path.join("big").join("f"), [0; 300]).unwrap();
+        fs::write(path.join("small").join("f"), [0; 100]).unwrap();
+
+        let mut children = get_file_by_uid(dir).unwrap().get_children(true);
+        sort_files(&mut children, ColumnKind::TotalSize, false);
+
+        assert_eq!(children.iter().map(|f| f.name.as_str()).collect::<Vec<_>>(), vec!["empty", "small", "big"]);
+        assert_eq!(get_total_dir_size_fast(&path.to_string_lossy()), 400);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn iterate_paths_with_drive_letters() {
+        let _globals = lock_globals();
+        let (_, dir) = make_temp_dir("drive", &[]);
+        let drive = std::env::var("SystemDrive").unwrap_or(String::from("C:"));
+
+        let found = iterate_paths(dir, &[drive.as_str()]).unwrap();
+        assert_eq!(get_file_by_uid(found).unwrap().name, drive);
+
+        let found = iterate_paths(dir, &split_path_input(&format!(r"{drive}\Users"))).unwrap();
+        assert_eq!(get_file_by_uid(found).unwrap().name, "Users");
+    }
+}
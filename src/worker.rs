@@ -0,0 +1,336 @@
+use crate::IS_MASTER_WORKING;
+use crate::print::try_extract_utf8_text;
+use crate::uid::Uid;
+use crate::utils::{get_file_by_uid, get_total_dir_size_fast, try_get_path_by_uid};
+use crossbeam_channel::{unbounded, Receiver, RecvTimeoutError, Sender};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime};
+
+pub const WORKER_NUM: usize = 4;
+
+// workers kill themselves if the master has been idle for this long
+const IDLE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// workers don't read files bigger than this to count words
+const MAX_WORD_COUNT_SIZE: u64 = 1 << 26;
+
+pub static mut WORKER_POOL: *mut WorkerPool = std::ptr::null_mut();
+
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
+pub enum WorkItem {
+    ComputeRecursiveSize(Uid),
+    ComputeChecksum(Uid),
+    ComputeWordCount(Uid),
+    ComputeLastOpened(Uid),
+}
+
+enum WorkResult {
+    RecursiveSize(Uid, u64),
+    Checksum(Uid, Option<[u8; 32]>),
+    WordCount(Uid, Option<(u32, u32, u64)>),
+    LastOpened(Uid, Option<SystemTime>),
+}
+
+impl WorkResult {
+    fn work_item(&self) -> WorkItem {
+        match self {
+            WorkResult::RecursiveSize(uid, _) => WorkItem::ComputeRecursiveSize(*uid),
+            WorkResult::Checksum(uid, _) => WorkItem::ComputeChecksum(*uid),
+            WorkResult::WordCount(uid, _) => WorkItem::ComputeWordCount(*uid),
+            WorkResult::LastOpened(uid, _) => WorkItem::ComputeLastOpened(*uid),
+        }
+    }
+}
+
+// `FILES` and `PATHS` are not thread-safe, so workers never touch them.
+// Workers only touch the file system, and the master applies the results
+// to `FILES` when it calls `WorkerPool::collect_results`.
+pub struct WorkerPool {
+    worker_num: usize,
+    alive_workers: Arc<AtomicUsize>,
+    handles: Vec<JoinHandle<()>>,
+
+    // the path is resolved by the master, because `PATHS` is not thread-safe
+    work_sender: Sender<(WorkItem, String)>,
+    work_receiver: Receiver<(WorkItem, String)>,
+    result_sender: Sender<WorkResult>,
+    result_receiver: Receiver<WorkResult>,
+
+    // it doesn't send the same item multiple times
+    pending: HashSet<WorkItem>,
+}
+
+impl WorkerPool {
+    pub fn new(worker_num: usize) -> Self {
+        let (work_sender, work_receiver) = unbounded();
+        let (result_sender, result_receiver) = unbounded();
+
+        let mut result = WorkerPool {
+            worker_num,
+            alive_workers: Arc::new(AtomicUsize::new(0)),
+            handles: Vec::with_capacity(worker_num),
+            work_sender,
+            work_receiver,
+            result_sender,
+            result_receiver,
+            pending: HashSet::new(),
+        };
+
+        result.spawn_workers();
+        result
+    }
+
+    pub fn send(&mut self, item: WorkItem) {
+        if self.pending.contains(&item) {
+            return;
+        }
+
+        let uid = match item {
+            WorkItem::ComputeRecursiveSize(uid)
+            | WorkItem::ComputeChecksum(uid)
+            | WorkItem::ComputeWordCount(uid)
+            | WorkItem::ComputeLastOpened(uid) => uid,
+        };
+
+        let path = match try_get_path_by_uid(uid) {
+            Some(path) => path.to_string(),
+            None => {
+                return;
+            },
+        };
+
+        // workers might have killed themselves while the master was idle
+        if self.alive_workers.load(Ordering::SeqCst) == 0 {
+            self.spawn_workers();
+        }
+
+        // it never fails because `self` owns a receiver
+        self.work_sender.send((item, path)).unwrap();
+        self.pending.insert(item);
+    }
+
+    pub fn is_pending(&self, item: WorkItem) -> bool {
+        self.pending.contains(&item)
+    }
+
+    // It applies the finished works to `FILES`.
+    // It must be called by the master. It returns the number of the applied results.
+    pub fn collect_results(&mut self) -> usize {
+        let mut count = 0;
+
+        while let Ok(result) = self.result_receiver.try_recv() {
+            self.pending.remove(&result.work_item());
+            count += 1;
+
+            match result {
+                WorkResult::RecursiveSize(uid, size) => if let Some(file) = get_file_by_uid(uid) {
+                    file.recursive_size = Some(size);
+                },
+                WorkResult::Checksum(uid, checksum) => if let Some(file) = get_file_by_uid(uid) {
+                    if checksum.is_some() {
+                        file.checksum = checksum;
+                    }
+                },
+                WorkResult::WordCount(uid, wc) => if let Some(file) = get_file_by_uid(uid) {
+                    file.wc = Some(wc);
+                },
+                WorkResult::LastOpened(uid, last_opened) => if let Some(file) = get_file_by_uid(uid) {
+                    if last_opened.is_some() {
+                        file.last_opened = last_opened;
+                    }
+                },
+            }
+        }
+
+        count
+    }
+
+    pub fn shutdown(self) {
+        let WorkerPool { handles, work_sender, work_receiver, .. } = self;
+
+        // workers leave the loop when the channel is disconnected
+        drop(work_sender);
+        drop(work_receiver);
+
+        for handle in handles.into_iter() {
+            let _ = handle.join();
+        }
+    }
+
+    fn spawn_workers(&mut self) {
+        self.handles.retain(|handle| !handle.is_finished());
+
+        while self.alive_workers.load(Ordering::SeqCst) < self.worker_num {
+            let work_receiver = self.work_receiver.clone();
+            let result_sender = self.result_sender.clone();
+            let alive_workers = self.alive_workers.clone();
+
+            alive_workers.fetch_add(1, Ordering::SeqCst);
+
+            self.handles.push(thread::spawn(move || {
+                worker_loop(work_receiver, result_sender);
+                alive_workers.fetch_sub(1, Ordering::SeqCst);
+            }));
+        }
+    }
+}
+
+// It's a no-op if the pool is not initialized yet, and it returns false in that case.
+pub fn send_work(item: WorkItem) -> bool {
+    match unsafe { WORKER_POOL.as_mut() } {
+        Some(pool) => {
+            pool.send(item);
+            true
+        },
+        None => false,
+    }
+}
+
+// It's always false if the pool is not initialized yet.
+pub fn is_work_pending(item: WorkItem) -> bool {
+    match unsafe { WORKER_POOL.as_ref() } {
+        Some(pool) => pool.is_pending(item),
+        None => false,
+    }
+}
+
+// It's a no-op if the pool is not initialized yet.
+pub fn collect_work_results() -> usize {
+    match unsafe { WORKER_POOL.as_mut() } {
+        Some(pool) => pool.collect_results(),
+        None => 0,
+    }
+}
+
+fn worker_loop(
+    work_receiver: Receiver<(WorkItem, String)>,
+    result_sender: Sender<WorkResult>,
+) {
+    let mut last_active = Instant::now();
+
+    loop {
+        match work_receiver.recv_timeout(Duration::from_millis(500)) {
+            Ok((item, path)) => {
+                let result = run_work(item, &path);
+                last_active = Instant::now();
+
+                if result_sender.send(result).is_err() {
+                    break;
+                }
+            },
+            Err(RecvTimeoutError::Timeout) => {
+                if unsafe { IS_MASTER_WORKING } {
+                    last_active = Instant::now();
+                }
+
+                else if Instant::now().duration_since(last_active) > IDLE_TIMEOUT {
+                    break;
+                }
+            },
+            Err(RecvTimeoutError::Disconnected) => {
+                break;
+            },
+        }
+    }
+}
+
+fn run_work(item: WorkItem, path: &str) -> WorkResult {
+    match item {
+        WorkItem::ComputeRecursiveSize(uid) => WorkResult::RecursiveSize(
+            uid,
+            get_total_dir_size_fast(path),
+        ),
+        WorkItem::ComputeChecksum(uid) => WorkResult::Checksum(
+            uid,
+            calc_checksum(path),
+        ),
+        WorkItem::ComputeWordCount(uid) => WorkResult::WordCount(
+            uid,
+            calc_word_count(path),
+        ),
+        WorkItem::ComputeLastOpened(uid) => WorkResult::LastOpened(
+            uid,
+            calc_last_opened(path),
+        ),
+    }
+}
+
+// sha256 of the contents
+// it streams the file into the hasher, because `;D` might hash several huge files at once
+fn calc_checksum(path: &str) -> Option<[u8; 32]> {
+    let mut file = fs::File::open(path).ok()?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher).ok()?;
+
+    Some(hasher.finalize().into())
+}
+
+// `kMDItemLastUsedDate` of spotlight on macOS, and the access time otherwise
+// it falls back to the access time if spotlight doesn't know the file
+fn calc_last_opened(path: &str) -> Option<SystemTime> {
+    #[cfg(target_os = "macos")]
+    if let Ok(output) = std::process::Command::new("mdls").args(["-raw", "-name", "kMDItemLastUsedDate", path]).output() {
+        if let Some(last_used) = parse_mdls_date(&String::from_utf8_lossy(&output.stdout)) {
+            return Some(last_used);
+        }
+    }
+
+    fs::metadata(path).ok()?.accessed().ok()
+}
+
+// `mdls -raw` prints dates like `2024-03-01 12:34:56 +0000`, and `(null)` if there's no value
+#[cfg(target_os = "macos")]
+fn parse_mdls_date(s: &str) -> Option<SystemTime> {
+    let mut parts = s.trim().split(' ');
+    let date = parts.next()?.split('-').map(|n| n.parse::<i64>().ok()).collect::<Option<Vec<_>>>()?;
+    let time = parts.next()?.split(':').map(|n| n.parse::<i64>().ok()).collect::<Option<Vec<_>>>()?;
+    let offset = parts.next()?;
+
+    if date.len() != 3 || time.len() != 3 || offset.len() != 5 {
+        return None;
+    }
+
+    let offset_sign = if offset.starts_with('-') { -1 } else { 1 };
+    let offset_hours = offset.get(1..3)?.parse::<i64>().ok()?;
+    let offset_minutes = offset.get(3..5)?.parse::<i64>().ok()?;
+
+    // days since 1970-01-01 in the proleptic gregorian calendar
+    let (y, m, d) = (date[0], date[1], date[2]);
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((m + 9) % 12) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+
+    let secs = days * 86400 + time[0] * 3600 + time[1] * 60 + time[2]
+        - offset_sign * (offset_hours * 3600 + offset_minutes * 60);
+
+    Some(SystemTime::UNIX_EPOCH + Duration::from_secs(u64::try_from(secs).ok()?))
+}
+
+// (lines, words, bytes), like `wc`
+// it's `None` if the file is not a text file, or too big
+fn calc_word_count(path: &str) -> Option<(u32, u32, u64)> {
+    match fs::metadata(path) {
+        Ok(metadata) if metadata.is_file() && metadata.len() <= MAX_WORD_COUNT_SIZE => {},
+        _ => {
+            return None;
+        },
+    }
+
+    let content = fs::read(path).ok()?;
+    let text = try_extract_utf8_text(&content)?;
+
+    Some((
+        text.lines().count() as u32,
+        text.split_whitespace().count() as u32,
+        content.len() as u64,
+    ))
+}